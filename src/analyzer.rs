@@ -1,75 +1,632 @@
 //extern crate syn;
 mod callgraph;
 mod datas;
-mod function;
+mod disk_cache;
+mod file_index;
 mod name_resolver;
+mod parallel;
 mod parser_syn;
 mod project_infomation;
+mod symbol_table;
+
+pub use self::project_infomation::{locate_package, Targets};
 
 use self::callgraph::AnalyzerCallGraph;
-use self::function::AnalyzerFunction;
-use crate::call_info::CallInfo;
+use self::disk_cache::DiskCache;
+use self::file_index::FileIndex;
+use self::symbol_table::SymbolTable;
+use crate::call_info::{CallEdge, FunctionInfo, FunctionKind};
+use crate::filter::Scopes;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error;
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use syn::visit::Visit;
 
 // TODO: mod.rs wo module mei ni okikaeru
 
+pub struct Options {
+    /// analyze test code. (`#[cfg(test)]` modules)
+    pub include_tests: bool,
+    /// analyze only the files of these targets. (every .rs file in the directory if None)
+    pub targets: Option<Targets>,
+    /// store the results of each file in the target directory, and reuse them while
+    /// the file is not modified.
+    pub disk_cache: bool,
+    /// number of threads parsing the files. (the number of cores if None)
+    pub jobs: Option<usize>,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            include_tests: false,
+            targets: None,
+            disk_cache: false,
+            jobs: None,
+        }
+    }
+}
+
+/// Result of the analysis of a project.
+pub struct Analysis {
+    /// every call site.
+    pub calls: Vec<CallEdge>,
+    pub functions: Vec<FunctionInfo>,
+    /// modules and impl types of the functions.
+    pub scopes: Scopes,
+}
+
+/// Indexes of the files, reused while the file is not modified.
+pub struct Cache {
+    files: HashMap<(PathBuf, bool), (SystemTime, FileIndex)>,
+    /// files read by the last analysis, including the one which failed to parse.
+    sources: BTreeSet<PathBuf>,
+}
+
+impl Cache {
+    pub fn new() -> Cache {
+        Cache {
+            files: HashMap::new(),
+            sources: BTreeSet::new(),
+        }
+    }
+
+    /// Source files read by the last analysis with this cache.
+    pub fn sources(&self) -> &BTreeSet<PathBuf> {
+        &self.sources
+    }
+
+    /// Returns true if the index of `filename` is still valid.
+    fn is_fresh(&self, filename: &Path, include_tests: bool, modified: SystemTime) -> bool {
+        self.files
+            .get(&(filename.to_path_buf(), include_tests))
+            .is_some_and(|x| x.0 == modified)
+    }
+
+    fn insert(&mut self, include_tests: bool, modified: SystemTime, index: FileIndex) {
+        self.files
+            .insert((index.file.clone(), include_tests), (modified, index));
+    }
+
+    fn get(&self, filename: &Path, include_tests: bool) -> &FileIndex {
+        &self.files[&(filename.to_path_buf(), include_tests)].1
+    }
+}
+
+fn project_infomation(
+    directory: &PathBuf,
+    options: &Options,
+) -> Result<project_infomation::ProjectInfomaion, Box<dyn error::Error>> {
+    match &options.targets {
+        Some(targets) => project_infomation::ProjectInfomaion::with_targets(directory, targets),
+        None => project_infomation::ProjectInfomaion::new(directory),
+    }
+}
+
 //pub fn analyze(files: &Vec<PathBuf>) -> Result<Vec<CallInfo>, Box<dyn error::Error>> {
-pub fn analyze(directory: &PathBuf) -> Result<Vec<CallInfo>, Box<dyn error::Error>> {
-    let project_info = project_infomation::ProjectInfomaion::new(directory)?;
-    let mut result: Vec<CallInfo> = Vec::new();
-    let mut analyzer_funtions = AnalyzerFunction::new(project_info.project_name().to_string());
+/// Analyzes the project in `directory` in two phases.
+///
+/// 1. Each file is parsed once into a `FileIndex` of its items and call sites as written.
+///    Only the files modified since the last analysis with the same `cache` (and not stored in
+///    the disk cache) are parsed, on several threads. The files of the modules are found from
+///    the `mod` declarations in the indexes.
+/// 2. The call sites are resolved against the `SymbolTable` of the whole crate.
+pub fn analyze(
+    directory: &PathBuf,
+    options: &Options,
+    cache: &mut Cache,
+) -> Result<Analysis, Box<dyn error::Error>> {
+    cache.sources.clear();
+    let project_info = project_infomation(directory, options)?;
+    let project_name = project_info.project_name();
+
+    // phase 1
+    let indexer = Indexer::new(directory, &project_info, options);
+    let modules = indexer.find_modules(cache)?;
+
+    // phase 2
+    let indexes: Vec<&FileIndex> = modules
+        .files
+        .iter()
+        .map(|filename| cache.get(filename, options.include_tests))
+        .collect();
+    let table = SymbolTable::new(project_name, &indexes, &modules.paths);
+    let mut result: Vec<CallEdge> = Vec::new();
+    let mut functions: Vec<FunctionInfo> = Vec::new();
+    let mut scopes = Scopes::new(project_name);
+    for index in &indexes {
+        result.append(&mut table.resolve_calls(index));
+        functions.extend(index.functions.iter().cloned());
+        for name in &index.types {
+            scopes.add_type(name, &index.module);
+        }
+        for function in &index.functions {
+            // methods are named `Type::method`, and closures after their function
+            let ty = match function.kind {
+                FunctionKind::Function => None,
+                FunctionKind::Closure => function
+                    .name
+                    .split("::")
+                    .next()
+                    .filter(|x| index.types.iter().any(|ty| ty == x)),
+                _ => function.name.rsplit_once("::").map(|x| x.0),
+            };
+            scopes.add_function(&function.name, &index.module, ty);
+        }
+    }
+    mark_entry_points(&mut functions, &indexes, &modules, project_info.lib());
 
-    let files = project_info.source_files();
+    Ok(Analysis {
+        calls: result,
+        functions,
+        scopes,
+    })
+}
 
-    for filename in files {
-        let mut file = File::open(filename)?;
-        let mut src = String::new();
-        file.read_to_string(&mut src)?;
+/// Sets `is_exported` and `is_main` of the functions, which depend on the place of their files
+/// in the crate. `lib` is the root file of the library.
+fn mark_entry_points(
+    functions: &mut [FunctionInfo],
+    indexes: &[&FileIndex],
+    modules: &Modules,
+    lib: Option<&PathBuf>,
+) {
+    // paths re-exported by `pub use` in the public modules, and in any module.
+    // (`pub use a::f` exports a::f, and `pub use a::*` exports a)
+    let mut reexports: Vec<Vec<&str>> = Vec::new();
+    let mut inner_reexports: Vec<Vec<&str>> = Vec::new();
+    let mut public_types: HashSet<&str> = HashSet::new();
+    for index in indexes {
+        let is_exported = modules.exported.contains(&index.file);
+        for import in index.uses.iter().filter(|x| x.is_public) {
+            let path: Vec<&str> = import.path.split("::").collect();
+            if is_exported {
+                reexports.push(path.clone());
+            }
+            inner_reexports.push(path);
+        }
+        if is_exported {
+            public_types.extend(index.types.iter().map(|x| x.as_str()));
+        }
+    }
+    let is_reexported = |name: &str| reexports.iter().any(|path| path.last() == Some(&name));
 
-        let syntax = syn::parse_file(&src)?;
-        analyzer_funtions.visit_file(&syntax);
+    for function in functions {
+        let file = &function.location.file;
+        let (parent, name) = function
+            .name
+            .rsplit_once("::")
+            .unwrap_or(("", &function.name));
+        function.is_exported = function.is_exported
+            && match function.kind {
+                // free functions are named by the module, and methods by the type
+                // `pub use a::f` in lib.rs can export f of `pub use b::f` in a.rs
+                FunctionKind::Function => {
+                    modules.exported.contains(file)
+                        || is_reexported(parent)
+                        || (is_reexported(name)
+                            && inner_reexports
+                                .iter()
+                                .any(|path| path.ends_with(&[parent, name])))
+                }
+                _ => public_types.contains(parent) || is_reexported(parent),
+            };
+        let is_root = modules.paths.get(file).is_some_and(|x| x.is_empty());
+        function.is_main = function.kind == FunctionKind::Function
+            && name == "main"
+            && is_root
+            && Some(file) != lib;
     }
+}
+
+/// Source files of a project and the place of their modules.
+struct Modules {
+    files: Vec<PathBuf>,
+    /// path of the module of each file from the root of its crate.
+    /// (ex. `["analyzer", "callgraph"]`, or empty for the roots)
+    paths: HashMap<PathBuf, Vec<String>>,
+    /// the root of the library and the files of its public modules.
+    exported: HashSet<PathBuf>,
+}
 
-    for filename in files {
-        //
-        let mut funcs = AnalyzerFunction::new(project_info.project_name().to_string());
+/// The first phase of the analysis.
+struct Indexer<'a> {
+    project_info: &'a project_infomation::ProjectInfomaion,
+    options: &'a Options,
+    disk_cache: Option<DiskCache>,
+}
 
-        let resolver = name_resolver::NameResolver::new(filename)?;
-        let mut analyzer = AnalyzerCallGraph::new(resolver);
+impl<'a> Indexer<'a> {
+    fn new(
+        directory: &Path,
+        project_info: &'a project_infomation::ProjectInfomaion,
+        options: &'a Options,
+    ) -> Indexer<'a> {
+        let disk_cache = if options.disk_cache {
+            Some(DiskCache::new(directory))
+        } else {
+            None
+        };
+        Indexer {
+            project_info,
+            options,
+            disk_cache,
+        }
+    }
 
-        let mut file = File::open(filename)?;
-        let mut src = String::new();
-        file.read_to_string(&mut src)?;
+    /// Indexes the files of the project into `cache`, following the `mod` declarations from
+    /// the roots of the targets.
+    fn find_modules(&self, cache: &mut Cache) -> Result<Modules, Box<dyn error::Error>> {
+        // every file is indexed at once if they are known
+        if let Some(files) = self.project_info.source_files() {
+            self.index_files(files, cache)?;
+        }
 
-        let syntax = syn::parse_file(&src)?;
-        funcs.visit_file(&syntax);
-        analyzer.visit_file(&syntax);
+        let mut module_paths: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        let mut exported: HashSet<PathBuf> = self.project_info.lib().into_iter().cloned().collect();
+        let mut found: Vec<PathBuf> = Vec::new();
+        // (file, directory of the files of its child modules)
+        let mut level: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for root in self.project_info.roots() {
+            module_paths.insert(root.clone(), Vec::new());
+            let dir = root.parent().unwrap_or(Path::new("")).to_path_buf();
+            level.push((root.clone(), dir));
+        }
+        // the files of a level are indexed together, on several threads
+        while !level.is_empty() {
+            let files: Vec<PathBuf> = level.iter().map(|x| x.0.clone()).collect();
+            self.index_files(&files, cache)?;
 
-        /*
-        let tmp1_calls = analyzer.get_callinfo();
-        let tmp2_calls;
-        for tmp in tmp1_calls {
-            let y = None;
-            for func in funcs.get_function_list() {
-                if tmp.callee = func.func_name() {
-                    y = Some(func.name());
+            let mut next = Vec::new();
+            for (file, dir) in level {
+                let index = cache.get(&file, self.options.include_tests);
+                for decl in &index.mods {
+                    let (child, child_dir) =
+                        match project_infomation::module_file(&file, &dir, decl) {
+                            Some(x) => x,
+                            None => continue,
+                        };
+                    if module_paths.contains_key(&child) {
+                        continue;
+                    }
+                    let mut path = module_paths[&file].clone();
+                    path.extend(decl.path.iter().cloned());
+                    module_paths.insert(child.clone(), path);
+                    if decl.is_public && exported.contains(&file) {
+                        exported.insert(child.clone());
+                    }
+                    next.push((child, child_dir));
                 }
+                found.push(file);
+            }
+            level = next;
+        }
+
+        let files = match self.project_info.source_files() {
+            Some(files) => files.clone(),
+            None => found,
+        };
+        Ok(Modules {
+            files,
+            paths: module_paths,
+            exported,
+        })
+    }
+
+    /// Indexes the files which are not in `cache` or modified since.
+    fn index_files(
+        &self,
+        filenames: &[PathBuf],
+        cache: &mut Cache,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let include_tests = self.options.include_tests;
+        let project_name = self.project_info.project_name();
+        cache.sources.extend(filenames.iter().cloned());
+        let mut files: Vec<(&PathBuf, String, SystemTime, u64, String)> = Vec::new();
+        for filename in filenames {
+            let modified = fs::metadata(filename)?.modified()?;
+            if cache.is_fresh(filename, include_tests, modified) {
+                continue;
             }
 
-            match y {
-                Some(x) => tmps2_calls.push(xxx),
-                None => xxx,
+            // the names depend on the package and the module, and the calls in tests on
+            // include_tests
+            let src = fs::read_to_string(filename)?;
+            let module = self.project_info.module_name(filename)?;
+            let key = disk_cache::hash(&[
+                src.as_bytes(),
+                project_name.as_bytes(),
+                module.as_bytes(),
+                &[include_tests as u8],
+            ]);
+            match self
+                .disk_cache
+                .as_ref()
+                .and_then(|x| x.load(filename, include_tests, key))
+            {
+                Some(index) => cache.insert(include_tests, modified, index),
+                None => files.push((filename, module, modified, key, src)),
             }
         }
 
-        */
-        let mut calls = analyzer.get_callinfo();
-        result.append(&mut calls);
+        // errors are converted to strings to be sent from the threads
+        let jobs = self.options.jobs.unwrap_or_else(parallel::default_jobs);
+        let indexes = parallel::map(&files, jobs, |(filename, module, _, _, src)| {
+            index_file(filename, module, src, include_tests).map_err(|x| x.to_string())
+        });
+        // merge in the order of the files, which doesn't depend on the threads
+        for ((filename, _, modified, key, _), index) in files.iter().zip(indexes) {
+            let index = index.map_err(|x| format!("{}: {}", filename.display(), x))?;
+            if let Some(disk_cache) = &self.disk_cache {
+                disk_cache.store(filename, include_tests, *key, &index);
+            }
+            cache.insert(include_tests, *modified, index);
+        }
+        Ok(())
+    }
+}
+
+/// Parses the source of a file and collects its items and call sites.
+fn index_file(
+    filename: &Path,
+    module: &str,
+    src: &str,
+    include_tests: bool,
+) -> Result<FileIndex, Box<dyn error::Error>> {
+    let resolver = name_resolver::NameResolver::new(filename, module);
+    let mut analyzer = AnalyzerCallGraph::new(resolver, include_tests);
+
+    let syntax = syn::parse_file(src)?;
+    analyzer.visit_file(&syntax);
+    Ok(analyzer.into_index())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call_info::CallKind;
+    use crate::test_util;
+
+    #[test]
+    fn test_source_files() {
+        let dir = test_util::write_project(
+            "modules",
+            &[
+                ("Cargo.toml", "[package]\nname = \"pkg\"\n"),
+                (
+                    "src/lib.rs",
+                    "mod a;\nmod b {\n    mod c;\n}\n#[cfg(test)]\nmod missing;\n",
+                ),
+                ("src/a.rs", "mod d;\n"),
+                ("src/a/d.rs", "fn f() {\n    super::g();\n}\n"),
+                ("src/a/e.rs", ""),
+                ("src/b/c.rs", ""),
+                ("src/unused.rs", ""),
+                ("src/main.rs", ""),
+                ("src/bin/tool.rs", "#[path = \"other.rs\"]\nmod x;\n"),
+                ("src/bin/other.rs", ""),
+            ],
+        );
+        let source_files = |targets: Targets| -> Vec<String> {
+            let mut options = Options::new();
+            options.targets = Some(targets);
+            let mut cache = Cache::new();
+            analyze(&dir, &options, &mut cache).unwrap();
+            let mut names: Vec<String> = cache
+                .sources()
+                .iter()
+                .map(|x| x.strip_prefix(&dir).unwrap().display().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let lib = Targets {
+            lib: true,
+            ..Targets::default()
+        };
+        assert_eq!(
+            source_files(lib),
+            vec!["src/a.rs", "src/a/d.rs", "src/b/c.rs", "src/lib.rs"]
+        );
+        let bin = Targets {
+            bins: vec!["tool".to_string()],
+            ..Targets::default()
+        };
+        assert_eq!(
+            source_files(bin),
+            vec!["src/bin/other.rs", "src/bin/tool.rs"]
+        );
+        assert_eq!(source_files(Targets::default()).len(), 7);
+
+        // super:: of a/d.rs is the module a
+        let mut options = Options::new();
+        options.targets = Some(Targets::default());
+        let analysis = analyze(&dir, &options, &mut Cache::new()).unwrap();
+        let callees: Vec<&str> = analysis
+            .calls
+            .iter()
+            .map(|x| x.callinfo.callee.as_str())
+            .collect();
+        assert_eq!(callees, vec!["a::g"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_target_names() {
+        let dir = test_util::write_project(
+            "target-names",
+            &[
+                ("Cargo.toml", "[package]\nname = \"pkg\"\n"),
+                ("src/lib.rs", "pub fn api() {}\n"),
+                (
+                    "src/bin/tool/main.rs",
+                    "fn main() {\n    helper();\n}\nfn helper() {}\n",
+                ),
+                (
+                    "tests/it/main.rs",
+                    "fn main() {\n    check();\n}\nfn check() {}\n",
+                ),
+            ],
+        );
+        let calls = |targets: Targets| -> Vec<(String, String)> {
+            let mut options = Options::new();
+            options.targets = Some(targets);
+            let analysis = analyze(&dir, &options, &mut Cache::new()).unwrap();
+            let mut calls: Vec<(String, String)> = analysis
+                .calls
+                .iter()
+                .map(|x| (x.callinfo.caller.clone(), x.callinfo.callee.clone()))
+                .collect();
+            calls.sort();
+            calls
+        };
+        let call = |caller: &str, callee: &str| (caller.to_string(), callee.to_string());
+
+        assert_eq!(
+            calls(Targets::default()),
+            vec![call("tool::main", "tool::helper")]
+        );
+        let all = Targets {
+            all_targets: true,
+            ..Targets::default()
+        };
+        assert_eq!(
+            calls(all),
+            vec![
+                call("it::main", "it::check"),
+                call("tool::main", "tool::helper")
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_entry_points() {
+        let dir = test_util::write_project(
+            "entry-points",
+            &[
+                ("Cargo.toml", "[package]\nname = \"pkg\"\n"),
+                (
+                    "src/lib.rs",
+                    "pub mod api;\nmod private;\npub use private::{deep, reexported};\n\
+                     pub fn root() {}\nmod inline {\n    pub fn hidden() {}\n}\n",
+                ),
+                (
+                    "src/api.rs",
+                    "pub struct Api;\nimpl Api {\n    pub fn new() {}\n    fn helper() {}\n}\n",
+                ),
+                (
+                    "src/private.rs",
+                    "mod inner;\npub use self::inner::deep;\n\
+                     pub fn reexported() {}\npub fn internal() {}\npub(crate) fn krate() {}\n",
+                ),
+                ("src/private/inner.rs", "pub fn deep() {}\n"),
+                ("src/main.rs", "mod tool;\nfn main() {}\n"),
+                ("src/tool.rs", "pub fn main() {}\n"),
+            ],
+        );
+        let analysis = analyze(&dir, &Options::new(), &mut Cache::new()).unwrap();
+        let names = |filter: fn(&FunctionInfo) -> bool| -> Vec<String> {
+            let mut names: Vec<String> = analysis
+                .functions
+                .iter()
+                .filter(|x| filter(x))
+                .map(|x| {
+                    format!(
+                        "{}:{}",
+                        x.location.file.strip_prefix(&dir).unwrap().display(),
+                        x.name
+                    )
+                })
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            names(|x| x.is_exported),
+            vec![
+                "src/api.rs:Api::new",
+                "src/lib.rs:pkg::root",
+                "src/private.rs:private::reexported",
+                "src/private/inner.rs:inner::deep"
+            ]
+        );
+        assert_eq!(names(|x| x.is_main), vec!["src/main.rs:pkg::main"]);
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    Ok(result)
+    #[test]
+    fn test_calls_in_macros() {
+        let src = r#"
+            fn f(out: &mut String) {
+                println!("{}", a());
+                assert_eq!(b(1), c());
+                let v = vec![d(); 2];
+                writeln!(out, "{x}", x = e()).unwrap();
+            }
+            macro_rules! m {
+                ($x:expr) => { g($x) };
+            }
+        "#;
+        let index = index_file(Path::new("src/m.rs"), "m", src, false).unwrap();
+        let mut callees: Vec<&str> = index.calls.iter().map(|x| x.callee.as_str()).collect();
+        callees.sort();
+        assert_eq!(callees, vec!["a", "b", "c", "d", "e", "unwrap"]);
+    }
+
+    #[test]
+    fn test_closures() {
+        let src = r#"
+            fn f() {
+                let add = |x| g(x);
+                add(1);
+                let v: Vec<i32> = Vec::new();
+                v.iter().map(|x| h(x));
+            }
+        "#;
+        let index = index_file(Path::new("src/m.rs"), "m", src, false).unwrap();
+        let closure = &index.functions[1];
+        assert_eq!(
+            (closure.name.as_str(), closure.kind, closure.location.line),
+            ("m::f::add", FunctionKind::Closure, 3)
+        );
+        let calls: Vec<(&str, &str)> = index
+            .calls
+            .iter()
+            .map(|x| (x.caller.as_str(), x.callee.as_str()))
+            .collect();
+        assert!(calls.contains(&("m::f::add", "g")));
+        assert!(calls.contains(&("m::f", "m::f::add")));
+        assert!(calls.contains(&("m::f", "h")));
+
+        let table = SymbolTable::new("pkg", &[&index], &HashMap::new());
+        let edges = table.resolve_calls(&index);
+        assert!(edges
+            .iter()
+            .any(|x| x.callinfo.caller == "m::f" && x.callinfo.callee == "m::f::add"));
+    }
+
+    #[test]
+    fn test_trait_objects() {
+        let src = r#"
+            fn f(a: Box<dyn T>, b: Vec<Box<dyn T>>, c: &Arc<dyn T>) {
+                a.m();
+                b.len();
+                c.m();
+            }
+        "#;
+        let index = index_file(Path::new("src/m.rs"), "m", src, false).unwrap();
+        let kinds: Vec<CallKind> = index.calls.iter().map(|x| x.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![CallKind::Dynamic, CallKind::Method, CallKind::Dynamic]
+        );
+    }
 }
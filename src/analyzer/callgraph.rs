@@ -1,7 +1,9 @@
-use super::datas::FullStrcutName;
+use super::file_index::{FileIndex, Impl, ModDecl, RawCall, Use};
 use super::name_resolver::{NameResolver, VariableDefine};
 use super::parser_syn::SynStructName;
-use super::CallInfo;
+use crate::call_info::{CallKind, FunctionInfo, FunctionKind, Location};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
 
 enum KindCaller {
     Function(Vec<String>),
@@ -11,6 +13,7 @@ enum KindCaller {
 struct FnInfo {
     current_function: Option<KindCaller>,
     current_impl: Option<String>,
+    current_impl_is_trait: bool,
 }
 
 impl FnInfo {
@@ -18,6 +21,15 @@ impl FnInfo {
         FnInfo {
             current_function: None,
             current_impl: None,
+            current_impl_is_trait: false,
+        }
+    }
+
+    /// Names of the current function and the closures in it, or None outside of functions.
+    fn function_path(&mut self) -> Option<&mut Vec<String>> {
+        match &mut self.current_function {
+            Some(KindCaller::Function(path)) | Some(KindCaller::Method(path)) => Some(path),
+            None => None,
         }
     }
 
@@ -43,36 +55,166 @@ impl FnInfo {
     }
 }
 
+/// Collects the items and the call sites of a file into a `FileIndex`.
 pub struct AnalyzerCallGraph {
-    calls: Vec<CallInfo>,
+    index: FileIndex,
     status: FnInfo,
     local_variables: Vec<VariableDefine>,
     resolver: NameResolver,
+    include_tests: bool,
+    /// names of the inline modules around the current item. (`mod name { }`)
+    inline_modules: Vec<String>,
+    /// number of the inline modules around the current item without `pub`.
+    private_modules: usize,
+    /// (variable, full name) of the closures bound in the current function.
+    local_closures: Vec<(String, String)>,
 }
 
 impl AnalyzerCallGraph {
-    pub fn new(resolver: NameResolver) -> AnalyzerCallGraph {
-        let calls: Vec<CallInfo> = Vec::new();
+    /// If `include_tests` is false, modules with `#[cfg(test)]` are skipped.
+    pub fn new(resolver: NameResolver, include_tests: bool) -> AnalyzerCallGraph {
+        let index = FileIndex::new(
+            resolver.file_path().to_path_buf(),
+            resolver.resolve_callee(),
+        );
         let status = FnInfo::new();
         let local_variables: Vec<VariableDefine> = Vec::new();
         AnalyzerCallGraph {
-            calls,
+            index,
             status,
             local_variables,
             resolver,
+            include_tests,
+            inline_modules: Vec::new(),
+            private_modules: 0,
+            local_closures: Vec::new(),
         }
     }
 
-    fn push_callinfo(&mut self, callee: String) {
-        let callinfo = CallInfo {
-            callee,
+    /// Registers the current function as a definition.
+    fn push_function(
+        &mut self,
+        kind: FunctionKind,
+        attrs: &[syn::Attribute],
+        sig: &syn::Signature,
+        vis: &syn::Visibility,
+    ) {
+        let is_public = matches!(vis, syn::Visibility::Public(_));
+        let function = FunctionInfo {
+            name: self.status.get_caller_name(&self.resolver.resolve_caller()),
+            kind,
+            location: Location {
+                file: self.resolver.file_path().to_path_buf(),
+                line: sig.ident.span().start().line,
+            },
+            is_public,
+            is_test: has_attribute(attrs, "test"),
+            is_no_mangle: has_attribute(attrs, "no_mangle"),
+            is_extern: sig.abi.is_some(),
+            is_async: sig.asyncness.is_some(),
+            is_unsafe: sig.unsafety.is_some(),
+            // the second phase checks the modules of the file and the re-exports
+            is_exported: self.is_visible(is_public),
+            is_main: false,
+        };
+        self.index.functions.push(function);
+    }
+
+    /// Registers `let name = |..| ..;` as a closure of the current function, and visits its body
+    /// with the closure as the caller. Returns false for the other statements.
+    fn visit_named_closure(&mut self, node: &syn::Local) -> bool {
+        let (ident, closure) = match (&node.pat, node.init.as_ref().map(|x| &*x.1)) {
+            (syn::Pat::Ident(pat), Some(syn::Expr::Closure(closure))) => (&pat.ident, closure),
+            _ => return false,
+        };
+        match self.status.function_path() {
+            Some(path) => path.push(ident.to_string()),
+            None => return false,
+        }
+        let name = self.status.get_caller_name(&self.resolver.resolve_caller());
+        let function = FunctionInfo {
+            name: name.clone(),
+            kind: FunctionKind::Closure,
+            location: Location {
+                file: self.resolver.file_path().to_path_buf(),
+                line: ident.span().start().line,
+            },
+            is_public: false,
+            is_test: false,
+            is_no_mangle: false,
+            is_extern: false,
+            is_async: closure.asyncness.is_some(),
+            is_unsafe: false,
+            is_exported: false,
+            is_main: false,
+        };
+        self.index.functions.push(function);
+
+        syn::visit::visit_expr_closure(self, closure);
+        if let Some(path) = self.status.function_path() {
+            path.pop();
+        }
+        self.local_closures.push((ident.to_string(), name));
+        true
+    }
+
+    /// Returns true if an item declared with `pub` (or not) is public outside of the file.
+    fn is_visible(&self, is_public: bool) -> bool {
+        is_public && self.private_modules == 0
+    }
+
+    /// Registers the typed parameters of the current function as local variables.
+    fn push_parameters(&mut self, sig: &syn::Signature) {
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = input {
+                if let syn::Pat::Ident(ident) = &*pat_type.pat {
+                    let variable_type = match &*pat_type.ty {
+                        syn::Type::Path(ty) => Some(SynStructName::new(&ty.path).to_string()),
+                        _ => None,
+                    };
+                    let var = VariableDefine::new(
+                        ident.ident.to_string(),
+                        variable_type,
+                        is_trait_object(&pat_type.ty),
+                    );
+                    self.local_variables.push(var);
+                }
+            }
+        }
+    }
+
+    /// Replaces the leading `super` which points to an inline module of this file with `self`,
+    /// so that the second phase only sees the modules of the files.
+    /// ex. `super::f` in `mod tests { }` is `self::f`
+    fn outside_inline_modules(&self, path: &str) -> String {
+        let segments: Vec<&str> = path.split("::").collect();
+        let supers = segments.iter().take_while(|x| **x == "super").count();
+        let inner = supers.min(self.inline_modules.len());
+        if inner == 0 {
+            return path.to_string();
+        }
+        let mut result = if inner == supers {
+            vec!["self"]
+        } else {
+            Vec::new()
+        };
+        result.extend(&segments[inner..]);
+        result.join("::")
+    }
+
+    /// `span` is the position of the called name.
+    fn push_callinfo(&mut self, callee: String, kind: CallKind, span: proc_macro2::Span) {
+        let call = RawCall {
             caller: self.status.get_caller_name(&self.resolver.resolve_caller()),
+            callee,
+            kind,
+            line: span.start().line,
         };
-        self.calls.push(callinfo);
+        self.index.calls.push(call);
     }
 
-    pub fn get_callinfo(&self) -> Vec<CallInfo> {
-        self.calls.clone()
+    pub fn into_index(self) -> FileIndex {
+        self.index
     }
 }
 
@@ -84,50 +226,161 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
                 Some(n) => n.to_string(),
                 None => "".to_string(),
             };
-            if path_name == "cfg" && attr.tokens.to_string() == "(test)" {
+            if !self.include_tests && path_name == "cfg" && attr.tokens.to_string() == "(test)" {
                 return;
             }
         }
+
+        let name = node.ident.to_string();
+        let is_public = matches!(node.vis, syn::Visibility::Public(_));
+        if node.content.is_none() {
+            let mut path = self.inline_modules.clone();
+            path.push(name);
+            self.index.mods.push(ModDecl {
+                path,
+                file: path_attribute(&node.attrs),
+                is_public: self.is_visible(is_public),
+            });
+            return;
+        }
+        self.inline_modules.push(name);
+        if !is_public {
+            self.private_modules += 1;
+        }
         syn::visit::visit_item_mod(self, node);
+        if !is_public {
+            self.private_modules -= 1;
+        }
+        self.inline_modules.pop();
     }
 
     fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
         self.status.current_function = Some(KindCaller::Function(vec![node.sig.ident.to_string()]));
+        self.push_function(FunctionKind::Function, &node.attrs, &node.sig, &node.vis);
+        self.push_parameters(&node.sig);
         syn::visit::visit_item_fn(self, node);
         self.status.current_function = None;
         self.local_variables.clear();
+        self.local_closures.clear();
     }
 
     fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
         if let syn::Type::Path(type_path) = &*node.self_ty {
             let impl_name = SynStructName::new(&type_path.path);
             self.status.current_impl = Some(impl_name.to_string());
+            let trait_name = node
+                .trait_
+                .as_ref()
+                .and_then(|(_, path, _)| path.segments.last())
+                .map(|segment| segment.ident.to_string());
+            self.index.impls.push(Impl {
+                ty: impl_name.to_string(),
+                trait_name,
+            });
         }
+        self.status.current_impl_is_trait = node.trait_.is_some();
 
         syn::visit::visit_item_impl(self, node);
 
         self.status.current_impl = None;
+        self.status.current_impl_is_trait = false;
     }
 
     fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
         self.status.current_function = Some(KindCaller::Method(vec![node.sig.ident.to_string()]));
+        let kind = if self.status.current_impl_is_trait {
+            FunctionKind::TraitMethod
+        } else {
+            FunctionKind::Method
+        };
+        self.push_function(kind, &node.attrs, &node.sig, &node.vis);
+        self.push_parameters(&node.sig);
 
         syn::visit::visit_impl_item_method(self, node);
         self.status.current_function = None;
+        self.local_variables.clear();
+        self.local_closures.clear();
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        self.index.types.push(node.ident.to_string());
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        self.index.types.push(node.ident.to_string());
+        syn::visit::visit_item_enum(self, node);
+    }
+
+    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+        self.index.types.push(node.ident.to_string());
+        syn::visit::visit_item_union(self, node);
+    }
+
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        self.index.types.push(node.ident.to_string());
+        syn::visit::visit_item_type(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        self.index.traits.push(node.ident.to_string());
+        syn::visit::visit_item_trait(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        let mut uses = Vec::new();
+        push_uses(&node.tree, &mut Vec::new(), &mut uses);
+        let is_public = self.is_visible(matches!(node.vis, syn::Visibility::Public(_)));
+        for import in uses {
+            let path = self.outside_inline_modules(&import.path);
+            self.index.uses.push(Use {
+                path,
+                is_public,
+                ..import
+            });
+        }
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        // the arguments of macros like `format!`, `assert_eq!` and `vec!` are expressions.
+        // other macros, such as the bodies of `macro_rules!`, are not analyzed.
+        for expr in macro_arguments(&node.tokens) {
+            self.visit_expr(&expr);
+        }
+        syn::visit::visit_macro(self, node);
     }
 
     fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
         if let syn::Expr::Path(expr_path) = &*node.func {
+            // the last closure bound to the name
+            let closure = expr_path.path.get_ident().and_then(|ident| {
+                let closure = self.local_closures.iter().rev().find(|x| ident == &x.0)?;
+                Some((closure.1.clone(), ident.span()))
+            });
+            if let Some((name, span)) = closure {
+                self.push_callinfo(name, CallKind::Function, span);
+                syn::visit::visit_expr_call(self, node);
+                return;
+            }
             let callee = SynStructName::new(&expr_path.path);
             let mut callee_name = callee.name();
-            check_callee_path(&mut callee_name, &&self.resolver.resolve_callee());
-            self.push_callinfo(callee_name.fullname());
+            // Self::new() is a call of the type of the impl
+            if let Some(current_impl) = &self.status.current_impl {
+                callee_name.replace_first("Self", current_impl);
+            }
+            let span = match expr_path.path.segments.last() {
+                Some(segment) => segment.ident.span(),
+                None => proc_macro2::Span::call_site(),
+            };
+            let callee_name = self.outside_inline_modules(&callee_name.fullname());
+            self.push_callinfo(callee_name, CallKind::Function, span);
         }
         syn::visit::visit_expr_call(self, node);
     }
 
     fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
         let mut method_name = String::new();
+        let mut kind = CallKind::Method;
         if let syn::Expr::Path(expr_path) = &*node.receiver {
             let path_name = SynStructName::new(&expr_path.path);
             let receiver_name = path_name.to_string();
@@ -143,6 +396,9 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
             } else {
                 for v in &self.local_variables {
                     if v.same_name(&receiver_name) {
+                        if v.is_dyn() {
+                            kind = CallKind::Dynamic;
+                        }
                         let ty = v.variable_type();
                         if let Some(name) = ty {
                             method_name.push_str(&name);
@@ -154,7 +410,7 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
         }
 
         method_name.push_str(&(node.method.to_string()));
-        self.push_callinfo(method_name);
+        self.push_callinfo(method_name, kind, node.method.span());
 
         syn::visit::visit_expr_method_call(self, node);
     }
@@ -162,6 +418,9 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
     // syn::Local
     //   (enum)pat - ident -> name
     fn visit_local(&mut self, node: &'ast syn::Local) {
+        if self.visit_named_closure(node) {
+            return;
+        }
         // for explicit_declaration
         // ex. let var: Vec<String> = Vec::new()
         if let syn::Pat::Type(pat_type) = &node.pat {
@@ -173,7 +432,7 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
                     let struct_name = SynStructName::new(&ty.path);
                     variable_type = Some(struct_name.to_string());
                 }
-                let var = VariableDefine::new(name, variable_type);
+                let var = VariableDefine::new(name, variable_type, is_trait_object(&pat_type.ty));
                 self.local_variables.push(var);
             }
         } else if let syn::Pat::Ident(pat_ident) = &node.pat {
@@ -184,7 +443,7 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
                     let struct_name = SynStructName::new(&expr_path.path);
                     variable_type = Some(struct_name.to_string());
                 }
-                let var = VariableDefine::new(name, variable_type);
+                let var = VariableDefine::new(name, variable_type, false);
                 self.local_variables.push(var);
             }
         }
@@ -193,14 +452,106 @@ impl<'ast> syn::visit::Visit<'ast> for AnalyzerCallGraph {
     }
 }
 
-fn check_callee_path(callee: &mut FullStrcutName, module: &str) {
-    // use wo check site onaji kansu ga aruka
-    // file wo kakunin site onaji kansu mei ga aruka
-    // onaji dattara sentou ni module mei wo huyo
+/// Returns true if one of the attributes is `#[name]` or `#[path::name]`. (e.g. `#[tokio::test]`)
+fn has_attribute(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| match attr.path.segments.last() {
+        Some(segment) => segment.ident == name,
+        None => false,
+    })
+}
+
+/// Returns true if the type is a trait object, directly or behind a reference, `Box`, `Rc` or `Arc`.
+/// (e.g. `dyn Trait`, `&mut dyn Trait`, `Box<dyn Trait>`, `Rc<dyn Trait>`, but not `Vec<Box<dyn Trait>>`)
+fn is_trait_object(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::TraitObject(_) => true,
+        syn::Type::Reference(reference) => is_trait_object(&reference.elem),
+        syn::Type::Paren(paren) => is_trait_object(&paren.elem),
+        syn::Type::Group(group) => is_trait_object(&group.elem),
+        syn::Type::Path(path) => match path.path.segments.last() {
+            Some(segment) if matches!(segment.ident.to_string().as_str(), "Box" | "Rc" | "Arc") => {
+                match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(syn::GenericArgument::Type(ty)) => is_trait_object(ty),
+                        _ => false,
+                    },
+                    _ => false,
+                }
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Parses the tokens of a macro as expressions separated by `,` (`format!("{}", x)`) or by `;`
+/// (`vec![x; n]`). Returns nothing if they are not expressions.
+fn macro_arguments(tokens: &proc_macro2::TokenStream) -> Vec<syn::Expr> {
+    let comma = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+    let semicolon = Punctuated::<syn::Expr, syn::Token![;]>::parse_terminated;
+    match comma.parse2(tokens.clone()) {
+        Ok(exprs) => exprs.into_iter().collect(),
+        Err(_) => match semicolon.parse2(tokens.clone()) {
+            Ok(exprs) => exprs.into_iter().collect(),
+            Err(_) => Vec::new(),
+        },
+    }
+}
+
+/// `x` of `#[path = "x"]`.
+fn path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match attr.parse_meta() {
+        Ok(syn::Meta::NameValue(meta)) if meta.path.is_ident("path") => match meta.lit {
+            syn::Lit::Str(path) => Some(path.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
 
-    // zantei
-    callee.insert_first(module);
-    return;
+/// Flattens a use tree into the imported names and their paths.
+/// `prefix` is the path of the enclosing group. ex. `a::` of `use a::{b, c}`
+fn push_uses(tree: &syn::UseTree, prefix: &mut Vec<String>, uses: &mut Vec<Use>) {
+    let path = |name: &str| {
+        let mut path = prefix.clone();
+        path.push(name.to_string());
+        path.join("::")
+    };
+    match tree {
+        syn::UseTree::Path(use_path) => {
+            prefix.push(use_path.ident.to_string());
+            push_uses(&use_path.tree, prefix, uses);
+            prefix.pop();
+        }
+        syn::UseTree::Name(use_name) => {
+            let name = use_name.ident.to_string();
+            // `use a::b::{self}` imports b
+            let (name, path) = match (name.as_str(), prefix.last()) {
+                ("self", Some(last)) => (last.clone(), prefix.join("::")),
+                _ => (name.clone(), path(&name)),
+            };
+            uses.push(Use {
+                name,
+                path,
+                is_public: false,
+            });
+        }
+        syn::UseTree::Rename(rename) => uses.push(Use {
+            name: rename.rename.to_string(),
+            path: path(&rename.ident.to_string()),
+            is_public: false,
+        }),
+        syn::UseTree::Glob(_) => uses.push(Use {
+            name: "*".to_string(),
+            path: prefix.join("::"),
+            is_public: false,
+        }),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                push_uses(tree, prefix, uses);
+            }
+        }
+    }
 }
 
 /*
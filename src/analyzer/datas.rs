@@ -11,8 +11,13 @@ impl FullStrcutName {
         self.name.push(input.to_string());
     }
 
-    pub fn insert_first(&mut self, input: &str) {
-        self.name.insert(0, input.to_string())
+    /// Replaces the first segment if it is `from`.
+    pub fn replace_first(&mut self, from: &str, to: &str) {
+        if let Some(first) = self.name.first_mut() {
+            if first == from {
+                *first = to.to_string();
+            }
+        }
     }
 
     pub fn fullname(&self) -> String {
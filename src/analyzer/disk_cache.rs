@@ -0,0 +1,361 @@
+//! disk_cache is a module for storing the index of each file under the target directory.
+//!
+//! Each source file has a cache file named by the hash of its path and whether the tests are
+//! analyzed, so both analyses are kept. The first line is the key,
+//! the hash of the content and everything else the index depends on. It is followed by a line of
+//! tab separated fields for each item of the `FileIndex`: (tabs are shown as spaces)
+//!
+//! ```text
+//! key 1b2c3d4e5f607182
+//! module krate
+//! type Config
+//! trait Render
+//! impl Config Render
+//! use 0 helper crate::util::helper
+//! mod 1 db
+//! mod 0 ui::widgets widgets.rs
+//! fn function 10 1000001 krate::f
+//! call method 12 krate::f Config::load
+//! ```
+use super::file_index::{FileIndex, Impl, ModDecl, RawCall, Use};
+use crate::call_info::{CallKind, FunctionInfo, FunctionKind, Location};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Bump it when the lines of the cache file, or the fields of `FileIndex` and the values the
+/// analyzer puts in them, change. The cache of other versions is deleted.
+const FORMAT_VERSION: u32 = 5;
+
+/// Numbers the temporary files of `DiskCache::store` within the process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub struct DiskCache {
+    directory: PathBuf,
+}
+
+impl DiskCache {
+    /// Cache in the target directory of the package in `project_path`.
+    /// ($CARGO_TARGET_DIR, or `target` next to Cargo.lock of the workspace)
+    pub fn new(project_path: &Path) -> DiskCache {
+        let target = match env::var_os("CARGO_TARGET_DIR") {
+            Some(target) => PathBuf::from(target),
+            None => project_path
+                .ancestors()
+                .find(|x| x.join("Cargo.lock").is_file())
+                .unwrap_or(project_path)
+                .join("target"),
+        };
+        let version = format!("{}-{}", env!("CARGO_PKG_VERSION"), FORMAT_VERSION);
+        let root = target.join("callgraph-cache");
+        remove_other_versions(&root, &version);
+        DiskCache {
+            directory: root.join(version),
+        }
+    }
+
+    /// Returns the index of `filename` if it was stored with the same `key`.
+    pub fn load(&self, filename: &Path, include_tests: bool, key: u64) -> Option<FileIndex> {
+        let text = fs::read_to_string(self.path(filename, include_tests)).ok()?;
+        let mut lines = text.lines();
+        if lines.next()? != format!("key {:016x}", key) {
+            return None;
+        }
+
+        let mut index = FileIndex::new(filename.to_path_buf(), String::new());
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let line = || fields.get(2)?.parse().ok();
+            match fields.as_slice() {
+                ["module", name] => index.module = name.to_string(),
+                ["type", name] => index.types.push(name.to_string()),
+                ["trait", name] => index.traits.push(name.to_string()),
+                ["impl", ty, trait_name] => index.impls.push(Impl {
+                    ty: ty.to_string(),
+                    trait_name: optional(trait_name),
+                }),
+                ["use", is_public, name, path] => index.uses.push(Use {
+                    name: name.to_string(),
+                    path: path.to_string(),
+                    is_public: *is_public == "1",
+                }),
+                ["mod", is_public, path, file] => index.mods.push(ModDecl {
+                    path: path.split("::").map(|x| x.to_string()).collect(),
+                    file: optional(file),
+                    is_public: *is_public == "1",
+                }),
+                ["fn", kind, _, flags, name] => {
+                    let flag = |idx: usize| flags.as_bytes().get(idx) == Some(&b'1');
+                    index.functions.push(FunctionInfo {
+                        name: name.to_string(),
+                        kind: function_kind(kind)?,
+                        location: Location {
+                            file: filename.to_path_buf(),
+                            line: line()?,
+                        },
+                        is_public: flag(0),
+                        is_test: flag(1),
+                        is_no_mangle: flag(2),
+                        is_extern: flag(3),
+                        is_async: flag(4),
+                        is_unsafe: flag(5),
+                        is_exported: flag(6),
+                        is_main: false,
+                    });
+                }
+                ["call", kind, _, caller, callee] => index.calls.push(RawCall {
+                    caller: caller.to_string(),
+                    callee: callee.to_string(),
+                    kind: call_kind(kind)?,
+                    line: line()?,
+                }),
+                _ => return None,
+            }
+        }
+        Some(index)
+    }
+
+    /// Stores the index of `filename`. The cache is only a speedup, so errors are ignored.
+    pub fn store(&self, filename: &Path, include_tests: bool, key: u64, index: &FileIndex) {
+        let mut text = format!("key {:016x}\nmodule\t{}\n", key, index.module);
+        for name in &index.types {
+            text.push_str(&format!("type\t{}\n", name));
+        }
+        for name in &index.traits {
+            text.push_str(&format!("trait\t{}\n", name));
+        }
+        for item in &index.impls {
+            let trait_name = item.trait_name.as_deref().unwrap_or("");
+            text.push_str(&format!("impl\t{}\t{}\n", item.ty, trait_name));
+        }
+        for import in &index.uses {
+            text.push_str(&format!(
+                "use\t{}\t{}\t{}\n",
+                flag(import.is_public),
+                import.name,
+                import.path
+            ));
+        }
+        for decl in &index.mods {
+            let file = decl.file.as_deref().unwrap_or("");
+            text.push_str(&format!(
+                "mod\t{}\t{}\t{}\n",
+                flag(decl.is_public),
+                decl.path.join("::"),
+                file
+            ));
+        }
+        for function in &index.functions {
+            let flags: String = [
+                function.is_public,
+                function.is_test,
+                function.is_no_mangle,
+                function.is_extern,
+                function.is_async,
+                function.is_unsafe,
+                function.is_exported,
+            ]
+            .iter()
+            .map(|x| flag(*x))
+            .collect();
+            text.push_str(&format!(
+                "fn\t{}\t{}\t{}\t{}\n",
+                function.kind.as_str(),
+                function.location.line,
+                flags,
+                function.name
+            ));
+        }
+        for call in &index.calls {
+            text.push_str(&format!(
+                "call\t{}\t{}\t{}\t{}\n",
+                call.kind.as_str(),
+                call.line,
+                call.caller,
+                call.callee
+            ));
+        }
+
+        if fs::create_dir_all(&self.directory).is_err() {
+            return;
+        }
+        // write to a file of its own and rename it, so a concurrent run never reads a partial
+        // file and a failed write leaves the old one
+        let path = self.path(filename, include_tests);
+        let temp = path.with_extension(format!(
+            "{}-{}.tmp",
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if fs::write(&temp, text).is_err() || fs::rename(&temp, &path).is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+    }
+
+    fn path(&self, filename: &Path, include_tests: bool) -> PathBuf {
+        let name = filename.to_string_lossy();
+        self.directory.join(format!(
+            "{:016x}",
+            hash(&[name.as_bytes(), &[include_tests as u8]])
+        ))
+    }
+}
+
+/// Deletes the directories of the other versions in `root`. Errors are ignored.
+fn remove_other_versions(root: &Path, version: &str) {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        if entry.file_name() != version && entry.path().is_dir() {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash of the parts. It is stable between builds, unlike `DefaultHasher`.
+pub fn hash(parts: &[&[u8]]) -> u64 {
+    let mut result: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for byte in part.iter().chain(&[0xff]) {
+            result ^= u64::from(*byte);
+            result = result.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    result
+}
+
+/// Empty fields are None.
+fn optional(field: &str) -> Option<String> {
+    if field.is_empty() {
+        None
+    } else {
+        Some(field.to_string())
+    }
+}
+
+fn flag(value: bool) -> char {
+    if value {
+        '1'
+    } else {
+        '0'
+    }
+}
+
+fn call_kind(name: &str) -> Option<CallKind> {
+    [CallKind::Function, CallKind::Method, CallKind::Dynamic]
+        .iter()
+        .copied()
+        .find(|x| x.as_str() == name)
+}
+
+fn function_kind(name: &str) -> Option<FunctionKind> {
+    [
+        FunctionKind::Function,
+        FunctionKind::Method,
+        FunctionKind::TraitMethod,
+        FunctionKind::Closure,
+    ]
+    .iter()
+    .copied()
+    .find(|x| x.as_str() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn test_store_and_load() {
+        let directory =
+            env::temp_dir().join(format!("callgraph-cache-test-{}", std::process::id()));
+        let cache = DiskCache {
+            directory: directory.clone(),
+        };
+        let filename = Path::new("src/a.rs");
+        let mut index = FileIndex::new(filename.to_path_buf(), "a".to_string());
+        index.types.push("Type".to_string());
+        index.traits.push("Render".to_string());
+        index.uses.push(Use {
+            name: "*".to_string(),
+            path: "crate::b".to_string(),
+            is_public: true,
+        });
+        index.impls.push(Impl {
+            ty: "Type".to_string(),
+            trait_name: Some("Render".to_string()),
+        });
+        index.impls.push(Impl {
+            ty: "Type".to_string(),
+            trait_name: None,
+        });
+        index.mods.push(ModDecl {
+            path: vec!["inline".to_string(), "c".to_string()],
+            file: None,
+            is_public: true,
+        });
+        index.mods.push(ModDecl {
+            path: vec!["d".to_string()],
+            file: Some("other/d.rs".to_string()),
+            is_public: false,
+        });
+        let mut function = test_util::function("a::f", 3);
+        function.location.file = filename.to_path_buf();
+        function.is_public = true;
+        function.is_async = true;
+        function.is_exported = true;
+        index.functions.push(function);
+        index.calls.push(RawCall {
+            caller: "a::f".to_string(),
+            callee: "Type::g".to_string(),
+            kind: CallKind::Dynamic,
+            line: 4,
+        });
+
+        cache.store(filename, false, 1, &index);
+        assert_eq!(cache.load(filename, false, 1), Some(index.clone()));
+        assert!(cache.load(filename, false, 2).is_none());
+        assert!(cache.load(filename, true, 1).is_none());
+        assert!(cache.load(Path::new("src/b.rs"), false, 1).is_none());
+
+        // the index with the tests doesn't replace the other one
+        let mut with_tests = index.clone();
+        with_tests.types.push("Tests".to_string());
+        cache.store(filename, true, 3, &with_tests);
+        assert_eq!(cache.load(filename, false, 1), Some(index.clone()));
+        assert_eq!(cache.load(filename, true, 3), Some(with_tests));
+
+        // storing again replaces the file, and leaves no temporary file
+        cache.store(filename, false, 4, &index);
+        assert_eq!(cache.load(filename, false, 4), Some(index));
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 2);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_remove_other_versions() {
+        let root = env::temp_dir().join(format!("callgraph-cache-versions-{}", std::process::id()));
+        for version in &["0.1.0-1", "0.1.0-2"] {
+            fs::create_dir_all(root.join(version)).unwrap();
+        }
+        fs::write(root.join("file"), "").unwrap();
+
+        remove_other_versions(&root, "0.1.0-2");
+        assert!(!root.join("0.1.0-1").exists());
+        assert!(root.join("0.1.0-2").is_dir());
+        assert!(root.join("file").is_file());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_ne!(hash(&[b"ab", b"c"]), hash(&[b"a", b"bc"]));
+    }
+}
@@ -0,0 +1,81 @@
+//! file_index is a module for the items and the call sites found in a file.
+//!
+//! It is the result of the first phase of the analysis, made from the syntax tree of each file
+//! independently. The call sites are kept as written, and resolved in the second phase.
+use crate::call_info::{CallKind, FunctionInfo};
+use std::path::PathBuf;
+
+/// Items and unresolved call sites of a file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileIndex {
+    pub file: PathBuf,
+    /// name of the module of the file. (the file stem, or the package name for lib.rs and main.rs)
+    pub module: String,
+    pub functions: Vec<FunctionInfo>,
+    /// names of the structs, enums, unions and type aliases.
+    pub types: Vec<String>,
+    pub traits: Vec<String>,
+    pub impls: Vec<Impl>,
+    pub uses: Vec<Use>,
+    /// `mod name;` declarations of the modules in other files.
+    pub mods: Vec<ModDecl>,
+    pub calls: Vec<RawCall>,
+}
+
+impl FileIndex {
+    pub fn new(file: PathBuf, module: String) -> FileIndex {
+        FileIndex {
+            file,
+            module,
+            functions: Vec::new(),
+            types: Vec::new(),
+            traits: Vec::new(),
+            impls: Vec::new(),
+            uses: Vec::new(),
+            mods: Vec::new(),
+            calls: Vec::new(),
+        }
+    }
+}
+
+/// An impl block. ex. `impl Display for Config` is Impl { ty: "Config", trait_name: Some("Display") }
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Impl {
+    pub ty: String,
+    pub trait_name: Option<String>,
+}
+
+/// A module declared without its content. ex. `mod b { mod c; }` is ModDecl { path: ["b", "c"], .. }
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ModDecl {
+    /// names of the inline modules around the declaration, followed by the declared name.
+    pub path: Vec<String>,
+    /// `x` of `#[path = "x"]`.
+    pub file: Option<String>,
+    /// declared with `pub` in public inline modules.
+    pub is_public: bool,
+}
+
+/// A name imported by `use`. ex. `use crate::a::f as g;` is Use { name: "g", path: "crate::a::f" }
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Use {
+    /// the imported name, or "*" for a glob import.
+    pub name: String,
+    /// the path as written, except that the leading `super` of an inline module is `self`.
+    /// ex. `use super::f;` in `mod tests { }` is "self::f"
+    pub path: String,
+    /// re-exported with `pub use` in public inline modules.
+    pub is_public: bool,
+}
+
+/// A call site before the callee is resolved.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RawCall {
+    pub caller: String,
+    /// path of the called function as written (`a::f`), or the method with the type of
+    /// the receiver if it is known. (`Type::method`, `method`)
+    /// `super` of the inline modules in the file is already replaced. (see `Use::path`)
+    pub callee: String,
+    pub kind: CallKind,
+    pub line: usize,
+}
@@ -1,21 +1,19 @@
-use std::error;
-use std::ffi::OsString;
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
-use thiserror::Error;
+use std::path::{Path, PathBuf};
 
 pub struct VariableDefine {
     name: String,
     // TODO: Option ha huyou ??
     variable_type: Option<String>,
+    /// the type is a trait object. (e.g. `&dyn Trait`, `Box<dyn Trait>`)
+    is_dyn: bool,
 }
 
 impl VariableDefine {
-    pub fn new(name: String, variable_type: Option<String>) -> VariableDefine {
+    pub fn new(name: String, variable_type: Option<String>, is_dyn: bool) -> VariableDefine {
         VariableDefine {
             name,
             variable_type,
+            is_dyn,
         }
     }
 
@@ -26,58 +24,28 @@ impl VariableDefine {
     pub fn variable_type(&self) -> Option<String> {
         self.variable_type.clone()
     }
-}
-
-#[derive(Error, Debug)]
-pub enum ErrorKind {
-    #[error("Invalid location from lib.rs")]
-    InvalidLocLibRs,
-    #[error("Invalid package name in cargo.toml")]
-    InvalidPackageName,
-    #[error("Invalid filename")]
-    InvalidFilename,
-}
 
-/// Check the module name to which the file belongs.
-/// If filename is lib.rs, then we check the Cargo.toml
-pub fn get_full_class_path(file_path: &PathBuf) -> Result<String, Box<dyn error::Error>> {
-    let file_name = file_path.file_name().ok_or(ErrorKind::InvalidFilename)?;
-    if file_name == OsString::from("lib.rs") || file_name == OsString::from("main.rs") {
-        // If the file name is lib.rs, there is cargo.toml up two.
-        let parent_dir = file_path.parent().ok_or(ErrorKind::InvalidLocLibRs)?;
-        let cargo_dir = parent_dir.parent().ok_or(ErrorKind::InvalidLocLibRs)?;
-        let cargo_file = cargo_dir.join("Cargo.toml");
-
-        return get_project_name_from_cargo_toml(&cargo_file);
+    pub fn is_dyn(&self) -> bool {
+        self.is_dyn
     }
-    let result = file_path.file_stem().ok_or(ErrorKind::InvalidFilename)?;
-    let result = result.to_str().ok_or(ErrorKind::InvalidFilename)?;
-
-    Ok(result.to_string())
-}
-
-fn get_project_name_from_cargo_toml(cargo_file: &PathBuf) -> Result<String, Box<dyn error::Error>> {
-    let mut f = File::open(cargo_file)?;
-    let mut contents = String::new();
-    f.read_to_string(&mut contents)?;
-
-    let values = contents.parse::<toml::Value>()?;
-    let project_name = values["package"]["name"]
-        .as_str()
-        .ok_or(ErrorKind::InvalidPackageName)?;
-
-    Ok(String::from(project_name))
 }
 
 pub struct NameResolver {
     full_class_path: String,
+    file_path: PathBuf,
 }
 
 impl NameResolver {
-    pub fn new(file_path: &PathBuf) -> Result<NameResolver, Box<dyn error::Error>> {
-        let full_class_path = get_full_class_path(file_path)?;
+    /// `module` is the name of the module of the file. (see `ProjectInfomaion::module_name`)
+    pub fn new(file_path: &Path, module: &str) -> NameResolver {
+        NameResolver {
+            full_class_path: module.to_string(),
+            file_path: file_path.to_path_buf(),
+        }
+    }
 
-        Ok(NameResolver { full_class_path })
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
     }
 
     pub fn resolve_caller(&self) -> String {
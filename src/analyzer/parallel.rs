@@ -0,0 +1,59 @@
+//! parallel is a module for running the per-file analysis on several threads.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Number of threads used when it is not given. (the number of cores)
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |x| x.get())
+}
+
+/// Applies `f` to each item on `jobs` threads.
+/// The results are in the order of `items`, whichever thread finished first.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                if idx >= items.len() {
+                    break;
+                }
+                let result = f(&items[idx]);
+                results.lock().unwrap()[idx] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|x| x.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let items: Vec<usize> = (0..100).collect();
+        let expected: Vec<usize> = items.iter().map(|x| x * 2).collect();
+        for jobs in &[1, 3, 8, 200] {
+            assert_eq!(map(&items, *jobs, |x| x * 2), expected);
+        }
+        assert_eq!(map(&[] as &[usize], 4, |x| *x), Vec::<usize>::new());
+    }
+}
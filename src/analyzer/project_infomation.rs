@@ -1,84 +1,128 @@
+use super::file_index::ModDecl;
+use std::collections::{BTreeMap, HashMap};
 use std::error;
 use std::fs;
-use std::fs::File;
 use std::io;
-use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ErrorKind {
     #[error("Invalid package name in cargo.toml")]
     InvalidPackageName,
+    #[error("could not find Cargo.toml in the current directory or any parent directory")]
+    ManifestNotFound,
+    #[error("package `{0}` not found in the workspace")]
+    PackageNotFound(String),
+    #[error("the manifest is a virtual workspace. Choose a package with -p")]
+    VirtualManifest,
+    #[error("no library target in the package")]
+    LibNotFound,
+    #[error("no bin target named `{0}` in the package")]
+    BinNotFound(String),
+    #[error("invalid file name")]
+    InvalidFilename,
 }
 
-pub struct StructPath {
-    path: Vec<String>,
+/// Targets of a package to analyze, like the target selection of cargo.
+/// Without any selection, the library and all binaries are analyzed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Targets {
+    pub lib: bool,
+    /// names of the binaries.
+    pub bins: Vec<String>,
+    /// the library, binaries, tests, examples and benches.
+    pub all_targets: bool,
 }
-impl StructPath {
-    pub fn new() -> StructPath {
-        StructPath { path: Vec::new() }
-    }
-
-    pub fn push(&mut self, name: &str) {
-        self.path.push(String::from(name));
-    }
 
-    pub fn fullname(&self) -> String {
-        self.path.join("::")
-    }
-}
-pub struct SourceInfomation {
-    file_path: PathBuf,
+pub struct ProjectInfomaion {
+    project_name: String,
+    /// root files of the targets, whose modules are at the top of the crate.
+    roots: Vec<PathBuf>,
+    /// name of the module of each root: the package for the library and the main binary,
+    /// and the target name for the others.
+    root_names: HashMap<PathBuf, String>,
+    /// every .rs file in the directory, or None to follow the `mod` declarations from the roots.
+    source_files: Option<Vec<PathBuf>>,
+    /// root file of the library, if it is one of the roots.
+    lib: Option<PathBuf>,
 }
 
-impl SourceInfomation {
-    pub fn new(file_path: &PathBuf) -> SourceInfomation {
-        SourceInfomation {
-            file_path: file_path.clone(),
-        }
+impl ProjectInfomaion {
+    /// Every .rs file in the project, with the roots of all targets.
+    pub fn new(project_path: &PathBuf) -> Result<ProjectInfomaion, Box<dyn error::Error>> {
+        let all = Targets {
+            all_targets: true,
+            ..Targets::default()
+        };
+        let mut info = ProjectInfomaion::with_targets(project_path, &all)?;
+        info.source_files = Some(get_sourcefile(project_path)?);
+        Ok(info)
     }
 
-    pub fn file_path(&self) -> &PathBuf {
-        &self.file_path
-    }
+    /// The root files of the selected targets. The other files are found from their `mod`
+    /// declarations. (see `module_file`)
+    pub fn with_targets(
+        project_path: &Path,
+        targets: &Targets,
+    ) -> Result<ProjectInfomaion, Box<dyn error::Error>> {
+        let manifest: toml::Value = fs::read_to_string(project_path.join("Cargo.toml"))?.parse()?;
+        let project_name = manifest
+            .get("package")
+            .and_then(|x| x.get("name"))
+            .and_then(|x| x.as_str())
+            .ok_or(ErrorKind::InvalidPackageName)?
+            .to_string();
 
-    pub fn get_struct_path(&self) -> StructPath {
-        let mut result = StructPath::new();
+        let lib = lib_root(project_path, &manifest);
+        let bins = bin_roots(project_path, &manifest, &project_name);
+        let select_all = !targets.lib && targets.bins.is_empty();
 
-        if let Some(parent_path) = self.file_path.parent() {
-            // Convert path components to module path
-            for component in parent_path.components() {
-                if let std::path::Component::Normal(os_str) = component {
-                    if let Some(component_str) = os_str.to_str() {
-                        result.push(component_str);
+        let mut root_names: HashMap<PathBuf, String> = HashMap::new();
+        if let Some(lib) = &lib {
+            root_names.insert(lib.clone(), project_name.clone());
+        }
+        for (name, root) in &bins {
+            root_names.insert(root.clone(), name.clone());
+        }
+
+        let mut roots: Vec<PathBuf> = Vec::new();
+        if targets.lib || targets.all_targets || select_all {
+            match &lib {
+                Some(lib) => roots.push(lib.clone()),
+                None if targets.lib => return Err(Box::new(ErrorKind::LibNotFound)),
+                None => {}
+            }
+        }
+        if targets.all_targets || select_all {
+            roots.extend(bins.values().cloned());
+        }
+        for name in &targets.bins {
+            let root = bins
+                .get(name)
+                .ok_or_else(|| ErrorKind::BinNotFound(name.clone()))?;
+            if !roots.contains(root) {
+                roots.push(root.clone());
+            }
+        }
+        if targets.all_targets {
+            for dir in &["tests", "examples", "benches"] {
+                for root in target_files(&project_path.join(dir))? {
+                    if let Some(name) = target_name(&root) {
+                        root_names.insert(root.clone(), name.to_string());
                     }
+                    roots.push(root);
                 }
             }
         }
-
-        result
-    }
-}
-
-pub struct ProjectInfomaion {
-    project_name: String,
-    source_files: Vec<PathBuf>,
-}
-
-impl ProjectInfomaion {
-    pub fn new(project_path: &PathBuf) -> Result<ProjectInfomaion, Box<dyn error::Error>> {
-        let cargo_file = project_path.join("Cargo.toml");
-        let mut f = File::open(&cargo_file)?;
-        let mut contents = String::new();
-        f.read_to_string(&mut contents)?;
-
-        let project_name = get_project_name_from_cargo_toml(&contents)?;
-        let source_files = get_sourcefile(project_path)?;
+        let lib = lib.filter(|lib| roots.contains(lib));
 
         Ok(ProjectInfomaion {
             project_name,
-            source_files,
+            roots,
+            root_names,
+            source_files: None,
+            lib,
         })
     }
 
@@ -86,8 +130,26 @@ impl ProjectInfomaion {
         self.project_name.as_str()
     }
 
-    pub fn source_files(&self) -> &Vec<PathBuf> {
-        &self.source_files
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Name of the module of the file: the name of the target for the roots, and the file
+    /// stem for the others.
+    pub fn module_name(&self, file: &Path) -> Result<String, Box<dyn error::Error>> {
+        if let Some(name) = self.root_names.get(file) {
+            return Ok(name.clone());
+        }
+        let stem = file.file_stem().and_then(|x| x.to_str());
+        Ok(stem.ok_or(ErrorKind::InvalidFilename)?.to_string())
+    }
+
+    pub fn source_files(&self) -> Option<&Vec<PathBuf>> {
+        self.source_files.as_ref()
+    }
+
+    pub fn lib(&self) -> Option<&PathBuf> {
+        self.lib.as_ref()
     }
 }
 
@@ -104,6 +166,194 @@ fn get_project_name_from_cargo_toml(
     Ok(String::from(project_name))
 }
 
+/// Returns the directory of the package to analyze, like cargo does.
+/// `manifest_path` is Cargo.toml of the package or the workspace,
+/// found in the current directory or its parents if None.
+/// `package` chooses a member of the workspace.
+pub fn locate_package(
+    manifest_path: Option<&Path>,
+    package: Option<&str>,
+) -> Result<PathBuf, Box<dyn error::Error>> {
+    let manifest_path = match manifest_path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let current = std::env::current_dir()?;
+            current
+                .ancestors()
+                .map(|dir| dir.join("Cargo.toml"))
+                .find(|path| path.is_file())
+                .ok_or(ErrorKind::ManifestNotFound)?
+        }
+    };
+    let root = manifest_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let manifest: toml::Value = fs::read_to_string(&manifest_path)?.parse()?;
+    let name = manifest
+        .get("package")
+        .and_then(|x| x.get("name"))
+        .and_then(|x| x.as_str());
+
+    let package = match package {
+        Some(package) => package,
+        None => {
+            return name
+                .map(|_| root)
+                .ok_or_else(|| ErrorKind::VirtualManifest.into())
+        }
+    };
+    if name == Some(package) {
+        return Ok(root);
+    }
+
+    let members = manifest
+        .get("workspace")
+        .and_then(|x| x.get("members"))
+        .and_then(|x| x.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for member in members.iter().filter_map(|x| x.as_str()) {
+        // only a trailing "*" is supported. (e.g. "crates/*")
+        let dirs = match member.strip_suffix('*') {
+            Some(prefix) => match fs::read_dir(root.join(prefix)) {
+                Ok(entries) => entries.filter_map(|x| x.ok()).map(|x| x.path()).collect(),
+                Err(_) => Vec::new(),
+            },
+            None => vec![root.join(member)],
+        };
+        for dir in dirs {
+            let content = match fs::read_to_string(dir.join("Cargo.toml")) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            if get_project_name_from_cargo_toml(&content).ok().as_deref() == Some(package) {
+                return Ok(dir);
+            }
+        }
+    }
+    Err(Box::new(ErrorKind::PackageNotFound(package.to_string())))
+}
+
+/// `[lib] path` or src/lib.rs.
+fn lib_root(project_path: &Path, manifest: &toml::Value) -> Option<PathBuf> {
+    let path = match manifest
+        .get("lib")
+        .and_then(|x| x.get("path"))
+        .and_then(|x| x.as_str())
+    {
+        Some(path) => project_path.join(path),
+        None => project_path.join("src").join("lib.rs"),
+    };
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Root files of the binaries by name: `[[bin]]` entries, src/main.rs and src/bin.
+fn bin_roots(
+    project_path: &Path,
+    manifest: &toml::Value,
+    project_name: &str,
+) -> BTreeMap<String, PathBuf> {
+    let mut result = BTreeMap::new();
+    let main = project_path.join("src").join("main.rs");
+    if main.is_file() {
+        result.insert(project_name.to_string(), main);
+    }
+    if let Ok(files) = target_files(&project_path.join("src").join("bin")) {
+        for file in files {
+            if let Some(name) = target_name(&file) {
+                result.insert(name.to_string(), file.clone());
+            }
+        }
+    }
+
+    let bins = manifest.get("bin").and_then(|x| x.as_array());
+    for bin in bins.into_iter().flatten() {
+        let name = match bin.get("name").and_then(|x| x.as_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        let path = match bin.get("path").and_then(|x| x.as_str()) {
+            Some(path) => project_path.join(path),
+            None => match result.get(name) {
+                Some(path) => path.clone(),
+                None => project_path
+                    .join("src")
+                    .join("bin")
+                    .join(format!("{}.rs", name)),
+            },
+        };
+        result.insert(name.to_string(), path);
+    }
+    result
+}
+
+/// Root files of the targets in a directory like tests/: `name.rs` and `name/main.rs`.
+/// Name of the target of a root file in src/bin, tests, examples or benches.
+/// (`name.rs` or `name/main.rs`)
+fn target_name(file: &Path) -> Option<&str> {
+    let name = match file.file_name().and_then(|x| x.to_str()) {
+        Some("main.rs") => file.parent().and_then(|x| x.file_name()),
+        _ => file.file_stem(),
+    };
+    name.and_then(|x| x.to_str())
+}
+
+fn target_files(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut result = Vec::new();
+    if !dir.is_dir() {
+        return Ok(result);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let main = path.join("main.rs");
+            if main.is_file() {
+                result.push(main);
+            }
+        } else if path.extension().is_some_and(|x| x == "rs") {
+            result.push(path);
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Returns the file of the module declared in `file`, and the directory of the files of
+/// its child modules, if the file exists. (modules behind a cfg may have no file)
+/// `dir` is the directory of the files of the child modules of `file`.
+pub fn module_file(file: &Path, dir: &Path, decl: &ModDecl) -> Option<(PathBuf, PathBuf)> {
+    let (name, inline) = decl.path.split_last()?;
+    let dir = inline.iter().fold(dir.to_path_buf(), |dir, x| dir.join(x));
+    let file_dir = file.parent().unwrap_or(&dir);
+
+    // name.rs has its children in name/, as name/mod.rs does
+    let (path, child_dir) = match &decl.file {
+        Some(path) => {
+            let path = file_dir.join(path);
+            let child_dir = path.parent().unwrap_or(file_dir).to_path_buf();
+            (path, child_dir)
+        }
+        None => {
+            let file = dir.join(format!("{}.rs", name));
+            if file.is_file() {
+                (file, dir.join(name))
+            } else {
+                (dir.join(name).join("mod.rs"), dir.join(name))
+            }
+        }
+    };
+    if path.is_file() {
+        Some((path, child_dir))
+    } else {
+        None
+    }
+}
+
 /// create a file list from the specified directory.
 /// Find files with extension rs recursively.
 fn get_sourcefile(path: &PathBuf) -> Result<Vec<PathBuf>, io::Error> {
@@ -124,20 +374,15 @@ fn get_sourcefile(path: &PathBuf) -> Result<Vec<PathBuf>, io::Error> {
             }
         }
     }
+    // the order of read_dir depends on the file system
+    result.sort();
 
     Ok(result)
 }
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn test_get_struct_path() {
-        let file_path = PathBuf::from("src/analyzer/project_infomation.rs");
-        let source_info = SourceInfomation::new(&file_path);
-        let struct_path = source_info.get_struct_path();
-        assert_eq!(struct_path.fullname(), "src::analyzer");
-    }
+    use crate::test_util;
 
     #[test]
     fn test_get_project_name_from_cargo_toml() {
@@ -150,4 +395,56 @@ mod tests {
             .expect("Failed to get project name from cargo.toml");
         assert_eq!(project_name, "test");
     }
+
+    #[test]
+    fn test_with_targets() {
+        let dir = test_util::write_project(
+            "targets",
+            &[
+                ("Cargo.toml", "[package]\nname = \"pkg\"\n"),
+                ("src/lib.rs", ""),
+                ("src/main.rs", ""),
+                ("src/bin/tool.rs", ""),
+                ("tests/t.rs", ""),
+            ],
+        );
+        let roots = |targets: &Targets| -> Vec<String> {
+            let info = ProjectInfomaion::with_targets(&dir, targets).unwrap();
+            let mut names: Vec<String> = info
+                .roots()
+                .iter()
+                .map(|x| x.strip_prefix(&dir).unwrap().display().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let lib = Targets {
+            lib: true,
+            ..Targets::default()
+        };
+        assert_eq!(roots(&lib), vec!["src/lib.rs"]);
+        let bin = Targets {
+            bins: vec!["tool".to_string()],
+            ..Targets::default()
+        };
+        assert_eq!(roots(&bin), vec!["src/bin/tool.rs"]);
+        assert_eq!(
+            roots(&Targets::default()),
+            vec!["src/bin/tool.rs", "src/lib.rs", "src/main.rs"]
+        );
+        let all = Targets {
+            all_targets: true,
+            ..Targets::default()
+        };
+        assert!(roots(&all).contains(&"tests/t.rs".to_string()));
+
+        let missing = Targets {
+            bins: vec!["nothing".to_string()],
+            ..Targets::default()
+        };
+        assert!(ProjectInfomaion::with_targets(&dir, &missing).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
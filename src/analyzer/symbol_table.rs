@@ -0,0 +1,273 @@
+//! symbol_table is a module for resolving the call sites against the items of the whole crate.
+//!
+//! It is the second phase of the analysis. The names follow the names of the definitions:
+//! functions are `module::function` and methods are `Type::method`.
+use super::file_index::{FileIndex, RawCall};
+use crate::call_info::{CallEdge, CallInfo, CallKind, FunctionKind, Location};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+/// Names defined in the crate.
+pub struct SymbolTable {
+    /// module of lib.rs and main.rs.
+    package: String,
+    modules: BTreeSet<String>,
+    /// module path of each file from the root of the crate. (ex. `["analyzer", "callgraph"]`)
+    module_paths: HashMap<PathBuf, Vec<String>>,
+    /// name of the module of each module path, which is the module of its file.
+    module_names: HashMap<Vec<String>, String>,
+    types: BTreeSet<String>,
+    traits: BTreeSet<String>,
+    /// types implementing each trait.
+    impls: BTreeMap<String, BTreeSet<String>>,
+    functions: BTreeSet<String>,
+    /// closures bound to local variables, whose calls are named in the first phase.
+    closures: BTreeSet<String>,
+}
+
+impl SymbolTable {
+    /// `module_paths` are the module paths of the files. Files without one are treated as
+    /// modules at the top of the crate.
+    pub fn new(
+        package: &str,
+        indexes: &[&FileIndex],
+        module_paths: &HashMap<PathBuf, Vec<String>>,
+    ) -> SymbolTable {
+        let mut table = SymbolTable {
+            package: package.to_string(),
+            modules: BTreeSet::new(),
+            module_paths: module_paths.clone(),
+            module_names: HashMap::new(),
+            types: BTreeSet::new(),
+            traits: BTreeSet::new(),
+            impls: BTreeMap::new(),
+            functions: BTreeSet::new(),
+            closures: BTreeSet::new(),
+        };
+        for index in indexes {
+            table.modules.insert(index.module.clone());
+            if let Some(path) = module_paths.get(&index.file) {
+                table
+                    .module_names
+                    .insert(path.clone(), index.module.clone());
+            }
+            table.types.extend(index.types.iter().cloned());
+            table.traits.extend(index.traits.iter().cloned());
+            for item in &index.impls {
+                if let Some(trait_name) = &item.trait_name {
+                    table
+                        .impls
+                        .entry(trait_name.clone())
+                        .or_default()
+                        .insert(item.ty.clone());
+                }
+            }
+            table
+                .functions
+                .extend(index.functions.iter().map(|x| x.name.clone()));
+            table.closures.extend(
+                index
+                    .functions
+                    .iter()
+                    .filter(|x| x.kind == FunctionKind::Closure)
+                    .map(|x| x.name.clone()),
+            );
+        }
+        table
+    }
+
+    /// Resolves the call sites of the file.
+    pub fn resolve_calls(&self, index: &FileIndex) -> Vec<CallEdge> {
+        index
+            .calls
+            .iter()
+            .map(|call| {
+                let callinfo = CallInfo {
+                    callee: self.resolve(index, call),
+                    caller: call.caller.clone(),
+                };
+                let location = Location {
+                    file: index.file.clone(),
+                    line: call.line,
+                };
+                CallEdge::new_call_site(callinfo, call.kind, location)
+            })
+            .collect()
+    }
+
+    /// Returns the full name of the callee.
+    /// Method calls are already named by the type of the receiver in the first phase.
+    pub fn resolve(&self, index: &FileIndex, call: &RawCall) -> String {
+        if call.kind != CallKind::Function || self.closures.contains(&call.callee) {
+            return call.callee.clone();
+        }
+        let written: Vec<&str> = call.callee.split("::").collect();
+
+        // replace the imported name with its path
+        let imported = index.uses.iter().find(|x| x.name == written[0]);
+        let mut path: Vec<&str> = match imported {
+            Some(import) => import.path.split("::").collect(),
+            None => vec![written[0]],
+        };
+        path.extend(&written[1..]);
+
+        // crate::, self:: and super:: are paths in this crate
+        let first = path[0];
+        let prefixes = path
+            .iter()
+            .take_while(|x| matches!(**x, "crate" | "self" | "super"))
+            .count();
+        let supers = path.iter().filter(|x| **x == "super").count();
+        let path = &path[prefixes..];
+        let is_local = prefixes > 0 || (path.len() >= 2 && self.modules.contains(path[0]));
+        let name = match path.last() {
+            Some(name) => *name,
+            None => return call.callee.clone(),
+        };
+
+        if path.len() >= 2 {
+            // methods are named by the type, and functions by the module
+            let parent = path[path.len() - 2];
+            // `Trait::method(x)` calls the method of the type if only one type implements it
+            if let Some(types) = self.impls.get(parent) {
+                if let (1, Some(ty)) = (types.len(), types.iter().next()) {
+                    let method = format!("{}::{}", ty, name);
+                    if self.functions.contains(&method) {
+                        return method;
+                    }
+                }
+            }
+            if self.types.contains(parent) || self.traits.contains(parent) || is_local {
+                return format!("{}::{}", parent, name);
+            }
+            // paths in the standard library are complete without an import
+            if imported.is_some() || matches!(path[0], "std" | "core" | "alloc") {
+                return path.join("::");
+            }
+        } else if prefixes > 0 {
+            let module = match first {
+                "crate" => self.package.clone(),
+                "self" => index.module.clone(),
+                _ => self.ancestor(index, supers),
+            };
+            return format!("{}::{}", module, name);
+        } else if !self
+            .functions
+            .contains(&format!("{}::{}", index.module, name))
+        {
+            // glob imports of the modules in this crate
+            for import in index.uses.iter().filter(|x| x.name == "*") {
+                if let Some(module) = import.path.rsplit("::").next() {
+                    let candidate = format!("{}::{}", module, name);
+                    if self.functions.contains(&candidate) {
+                        return candidate;
+                    }
+                }
+            }
+        }
+
+        format!("{}::{}", index.module, call.callee)
+    }
+
+    /// Returns the name of the module `level` levels above the module of the file.
+    fn ancestor(&self, index: &FileIndex, level: usize) -> String {
+        let path = match self.module_paths.get(&index.file) {
+            Some(path) => path,
+            None => return self.package.clone(),
+        };
+        let path = &path[..path.len().saturating_sub(level)];
+        match (self.module_names.get(path), path.last()) {
+            (Some(name), _) => name.clone(),
+            // an inline module of another file
+            (None, Some(name)) => name.clone(),
+            (None, None) => self.package.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::file_index::{Impl, Use};
+    use super::*;
+    use crate::test_util;
+    use std::path::PathBuf;
+
+    fn index(module: &str, functions: &[&str]) -> FileIndex {
+        let mut index = FileIndex::new(PathBuf::from(format!("{}.rs", module)), module.to_string());
+        for name in functions {
+            index.functions.push(test_util::function(name, 1));
+        }
+        index
+    }
+
+    fn call(callee: &str, kind: CallKind) -> RawCall {
+        RawCall {
+            caller: "main::main".to_string(),
+            callee: callee.to_string(),
+            kind,
+            line: 1,
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut main = index("krate", &["krate::main", "krate::run", "Config::load"]);
+        main.types.push("Config".to_string());
+        main.traits.push("Load".to_string());
+        main.impls.push(Impl {
+            ty: "Config".to_string(),
+            trait_name: Some("Load".to_string()),
+        });
+        for (name, path) in &[
+            ("helper", "crate::util::helper"),
+            ("swap", "std::mem::swap"),
+            ("db", "crate::db"),
+            ("*", "crate::glob"),
+        ] {
+            main.uses.push(Use {
+                name: name.to_string(),
+                path: path.to_string(),
+                is_public: false,
+            });
+        }
+        let util = index("util", &["util::helper"]);
+        let glob = index("glob", &["glob::from_glob"]);
+        let db = index("db", &["db::open"]);
+        let inner = index("inner", &["inner::f"]);
+        let module_paths: HashMap<PathBuf, Vec<String>> = vec![
+            (main.file.clone(), vec![]),
+            (util.file.clone(), vec!["util".to_string()]),
+            (
+                inner.file.clone(),
+                vec!["util".to_string(), "inner".to_string()],
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let table = SymbolTable::new("krate", &[&main, &util, &glob, &db, &inner], &module_paths);
+
+        let resolve = |callee: &str| table.resolve(&main, &call(callee, CallKind::Function));
+        assert_eq!(resolve("run"), "krate::run");
+        assert_eq!(resolve("helper"), "util::helper");
+        assert_eq!(resolve("swap"), "std::mem::swap");
+        assert_eq!(resolve("std::mem::drop"), "std::mem::drop");
+        assert_eq!(resolve("db::open"), "db::open");
+        assert_eq!(resolve("util::helper"), "util::helper");
+        assert_eq!(resolve("crate::util::helper"), "util::helper");
+        assert_eq!(resolve("self::run"), "krate::run");
+        assert_eq!(resolve("from_glob"), "glob::from_glob");
+        assert_eq!(resolve("Config::new"), "Config::new");
+        assert_eq!(resolve("Load::load"), "Config::load");
+        assert_eq!(resolve("Vec::new"), "krate::Vec::new");
+        assert_eq!(resolve("unknown"), "krate::unknown");
+
+        // super:: is the parent of the module of the file
+        let resolve_inner = |callee: &str| table.resolve(&inner, &call(callee, CallKind::Function));
+        assert_eq!(resolve_inner("super::helper"), "util::helper");
+        assert_eq!(resolve_inner("super::super::run"), "krate::run");
+        assert_eq!(resolve_inner("self::f"), "inner::f");
+
+        let method = call("Config::load", CallKind::Method);
+        assert_eq!(table.resolve(&main, &method), "Config::load");
+    }
+}
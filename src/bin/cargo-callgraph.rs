@@ -0,0 +1,42 @@
+//! `cargo callgraph`: runs as a cargo external subcommand.
+//! cargo calls `cargo-callgraph callgraph <args>`, so the first argument is the subcommand name.
+use clap::Parser;
+use rust_gen_callgraph::cli::{self, GlobalArgs, SubCommand};
+use rust_gen_callgraph::locate_package;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Callgraph(CallgraphArgs),
+}
+
+/// Genarate Callgraph for the current package.
+#[derive(clap::Args)]
+#[command(author, version, about, long_about = None)]
+struct CallgraphArgs {
+    /// package to analyze.
+    #[arg(short, long, global = true, value_name = "SPEC")]
+    package: Option<String>,
+    /// path to Cargo.toml.
+    #[arg(long, global = true, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+    #[command(flatten)]
+    global: GlobalArgs,
+    #[command(subcommand)]
+    command: SubCommand,
+}
+
+fn main() {
+    let Cargo::Callgraph(args) = Cargo::parse();
+
+    let result = match locate_package(args.manifest_path.as_deref(), args.package.as_deref()) {
+        Ok(directory) => cli::run(directory, args.global, args.command),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(2);
+    }
+    std::process::exit(0);
+}
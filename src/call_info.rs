@@ -1,4 +1,6 @@
-/// call_info is a module for storing call information.
+//! call_info is a module for storing call information.
+use std::fmt;
+use std::path::PathBuf;
 
 /// Represents a simple function call relationship in the call graph.
 ///
@@ -25,3 +27,143 @@ pub struct CallInfo {
     pub callee: String,
     pub caller: String,
 }
+
+/// Represents a call relationship after duplicate calls have been merged.
+///
+/// `count` is the number of `CallInfo` entries merged into this edge. When the graph is
+/// collapsed to a coarser granularity (e.g. module level), the counts of all merged calls
+/// are summed, so it can be used as the edge weight.
+/// `via` holds the names of the nodes which were removed by collapsing a linear chain
+/// `caller -> via[0] -> ... -> callee` into this edge.
+/// `locations` are the positions of the merged call sites.
+///
+/// # Example
+/// ```
+/// use rust_gen_callgraph::call_info::{CallEdge, CallInfo};
+/// let edge = CallEdge::new(CallInfo {
+///     caller: "my_project::foo".to_string(),
+///     callee: "my_project::bar".to_string(),
+/// });
+/// assert_eq!(edge.count, 1);
+/// ```
+#[derive(Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Clone)]
+pub struct CallEdge {
+    pub callinfo: CallInfo,
+    pub count: usize,
+    pub via: Vec<String>,
+    pub kind: CallKind,
+    pub locations: Vec<Location>,
+}
+
+impl CallEdge {
+    pub fn new(callinfo: CallInfo) -> CallEdge {
+        CallEdge {
+            callinfo,
+            count: 1,
+            via: Vec::new(),
+            kind: CallKind::Function,
+            locations: Vec::new(),
+        }
+    }
+
+    /// Creates an edge for a single call site.
+    pub fn new_call_site(callinfo: CallInfo, kind: CallKind, location: Location) -> CallEdge {
+        CallEdge {
+            kind,
+            locations: vec![location],
+            ..CallEdge::new(callinfo)
+        }
+    }
+}
+
+impl From<CallInfo> for CallEdge {
+    fn from(callinfo: CallInfo) -> CallEdge {
+        CallEdge::new(callinfo)
+    }
+}
+
+/// Syntax of a call.
+#[derive(Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Clone, Copy)]
+pub enum CallKind {
+    /// call of a path. (`func()`, `Type::func()`)
+    Function,
+    /// method call. (`value.method()`)
+    Method,
+    /// method call on a trait object. (`value.method()` where `value: &dyn Trait`)
+    Dynamic,
+}
+
+impl CallKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallKind::Function => "function",
+            CallKind::Method => "method",
+            CallKind::Dynamic => "dynamic",
+        }
+    }
+}
+
+/// Position in the source code.
+#[derive(Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Clone)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+/// Kind of a function definition.
+#[derive(Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Clone, Copy)]
+pub enum FunctionKind {
+    /// free function. (`fn func()`)
+    Function,
+    /// method in an inherent impl. (`impl Type { fn method() }`)
+    Method,
+    /// method in a trait impl. (`impl Trait for Type { fn method() }`)
+    TraitMethod,
+    /// closure bound to a local variable, named after the function defining it.
+    /// (`let name = |x| x + 1;` in `func` is `module::func::name`)
+    Closure,
+}
+
+impl FunctionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FunctionKind::Function => "function",
+            FunctionKind::Method => "method",
+            FunctionKind::TraitMethod => "trait_method",
+            FunctionKind::Closure => "closure",
+        }
+    }
+}
+
+/// Represents a function or method definition found in the project.
+///
+/// `name` is the fully qualified name, in the same form as `CallInfo::caller`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub kind: FunctionKind,
+    pub location: Location,
+    /// declared with `pub`. (`pub(crate)` etc. are not included)
+    pub is_public: bool,
+    /// has `#[test]` attribute.
+    pub is_test: bool,
+    /// has `#[no_mangle]` attribute.
+    pub is_no_mangle: bool,
+    /// declared with `extern`. (e.g. `extern "C" fn`)
+    pub is_extern: bool,
+    /// declared with `async`.
+    pub is_async: bool,
+    /// declared with `unsafe`.
+    pub is_unsafe: bool,
+    /// can be called from other crates: `pub` in the library, in public modules or re-exported
+    /// by `pub use`.
+    pub is_exported: bool,
+    /// `main` of a binary.
+    pub is_main: bool,
+}
@@ -0,0 +1,370 @@
+//! Command line arguments shared by the binaries.
+use crate::config::Config;
+use crate::{Command, Format, Granularity, MetricsFormat, MetricsSortKey, Options, Targets, Theme};
+use clap::Subcommand;
+use std::error;
+use std::fs;
+use std::path::PathBuf;
+
+/// Options shared by all subcommands.
+/// The switches which the config can set also take a value, so `--prune-leaves=false` turns off
+/// `prune_leaves = true` of the config.
+#[derive(clap::Args)]
+pub struct GlobalArgs {
+    /// configuration file. (default: callgraph.toml or [package.metadata.callgraph] of the project)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// apply the named preset of the configuration file.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub preset: Option<String>,
+    /// analyze only the library.
+    #[arg(long, global = true)]
+    pub lib: bool,
+    /// analyze only the binary. (can be repeated)
+    #[arg(long = "bin", global = true, value_name = "NAME")]
+    pub bins: Vec<String>,
+    /// analyze the library, binaries, tests, examples and benches.
+    #[arg(long, global = true)]
+    pub all_targets: bool,
+    /// don't read or write the cache of the analysis under target/.
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    /// number of threads parsing the files. [default: the number of cores]
+    #[arg(short, long, global = true, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+    /// keep running and run the command again when a source file is modified.
+    #[arg(long, global = true)]
+    pub watch: bool,
+    /// write to this file instead of stdout. .svg, .png and .pdf are rendered with Graphviz `dot`.
+    #[arg(short, long, global = true, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// print for data type. (default ignore) ex. Vec, String.
+    #[arg(long, global = true, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL")]
+    pub print_data_type: Option<bool>,
+    /// merge nodes at the chosen level and sum the edge weights. Calls to methods of unknown types are left out. [default: function]
+    #[arg(long, global = true, value_enum)]
+    pub granularity: Option<Granularity>,
+    /// drop the functions matching the pattern. `*` matches any string. (can be repeated) ex. std::*
+    #[arg(long, global = true, value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+    /// collapse each cycle into a single node.
+    #[arg(long, global = true, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL")]
+    pub collapse_cycles: Option<bool>,
+    /// drop A -> C when A -> B -> C exists.
+    #[arg(long, global = true, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL")]
+    pub transitive_reduction: Option<bool>,
+    /// drop leaf nodes which are called from only one caller.
+    #[arg(long, global = true, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL")]
+    pub prune_leaves: Option<bool>,
+    /// collapse linear chains of calls into one labeled edge.
+    #[arg(long, global = true, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL")]
+    pub collapse_chains: Option<bool>,
+}
+
+impl GlobalArgs {
+    /// Targets selected with --lib, --bin and --all-targets.
+    pub fn targets(&self) -> Targets {
+        Targets {
+            lib: self.lib,
+            bins: self.bins.clone(),
+            all_targets: self.all_targets,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum SubCommand {
+    /// render the call graph.
+    Graph {
+        /// output format of the graph. (default: inferred from the output file, or dot)
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// color the edges which are part of a cycle red.
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL")]
+        highlight_cycles: Option<bool>,
+        /// start the tree format from this function, or the pattern of the start points of --depth. (can be repeated)
+        #[arg(long = "root", value_name = "NAME")]
+        roots: Vec<String>,
+        /// show the callers instead of the callees in the tree format.
+        #[arg(long)]
+        invert: bool,
+        /// write the node table instead of the edge list in the csv and tsv formats.
+        #[arg(long)]
+        node_table: bool,
+        /// TOML file overriding the node shapes and colors of the dot format.
+        #[arg(long, value_name = "FILE")]
+        theme: Option<PathBuf>,
+        /// keep only the functions within this number of calls from the --root patterns.
+        #[arg(long)]
+        depth: Option<usize>,
+        /// serve the html viewer on this port of localhost, reloaded when a source file is modified.
+        #[arg(long, value_name = "PORT")]
+        serve: Option<u16>,
+    },
+    /// render a view declared in the config. ([views.<name>])
+    View {
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+        /// render every view into the directory given with -o, as <name>.<format>.
+        #[arg(long, requires = "output")]
+        all: bool,
+    },
+    /// print the functions calling the function.
+    Callers {
+        /// full name or its last segments. ex. main, Type::method
+        name: String,
+        /// include indirect callers up to this number of calls away.
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+    },
+    /// print the functions called by the function.
+    Callees {
+        /// full name or its last segments. ex. main, Type::method
+        name: String,
+        /// include indirect callees up to this number of calls away.
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+    },
+    /// print the call chains from a function to another.
+    Paths {
+        from: String,
+        to: String,
+        /// maximum number of the chains.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// print fan-in, fan-out, reach, betweenness and depth of functions, types and modules.
+    Metrics {
+        #[arg(long, value_enum, default_value_t = MetricsFormat::Csv)]
+        format: MetricsFormat,
+        /// sort the metrics by this key.
+        #[arg(long, value_enum, default_value_t = MetricsSortKey::Name)]
+        sort_by: MetricsSortKey,
+    },
+    /// print the functions unreachable from main, pub items and extern functions, marking the ones only tests reach.
+    DeadCode,
+    /// print direct recursion, mutual recursion and module cycles.
+    Cycles,
+    /// print the calls added and removed since another version of the project.
+    Diff {
+        /// path of the project to compare with.
+        base: PathBuf,
+    },
+}
+
+/// Runs the subcommand for the package in `directory`.
+pub fn run(
+    directory: PathBuf,
+    global: GlobalArgs,
+    command: SubCommand,
+) -> Result<(), Box<dyn error::Error>> {
+    let config = match &global.config {
+        Some(path) => Some(Config::load(path)?),
+        None => Config::find(&directory)?,
+    };
+    if config.is_none() && global.preset.is_some() {
+        return Err("--preset needs a configuration file".into());
+    }
+
+    if let SubCommand::View { name, all } = command {
+        let config = config.ok_or("no configuration file which declares the views")?;
+        let names = match name {
+            Some(name) => vec![name],
+            None => config.view_names().into_iter().map(String::from).collect(),
+        };
+        if all && global.watch {
+            return Err("--watch can't be used with view --all".into());
+        }
+        if all {
+            let output = global.output.clone().unwrap_or_default();
+            fs::create_dir_all(&output)?;
+        }
+        for name in names {
+            let mut options = options(Some(&config), Some(&name), &global)?;
+            if all {
+                let format = options.format.unwrap_or(Format::Dot);
+                let file = format!("{}.{}", name, format.extension());
+                options.output = global.output.as_ref().map(|x| x.join(file));
+            } else {
+                set_output(&mut options, &global);
+            }
+            check(&options)?;
+            if global.watch {
+                return crate::watch(&directory, &Command::Graph, &options, None);
+            }
+            crate::run(directory.clone(), &Command::Graph, &options)?;
+        }
+        return Ok(());
+    }
+
+    let mut options = options(config.as_ref(), None, &global)?;
+    set_output(&mut options, &global);
+    let mut serve = None;
+
+    let command = match command {
+        SubCommand::Graph {
+            format,
+            highlight_cycles,
+            roots,
+            invert,
+            node_table,
+            theme,
+            depth,
+            serve: port,
+        } => {
+            serve = port;
+            if format.is_some() {
+                options.format = format;
+            }
+            if let Some(highlight_cycles) = highlight_cycles {
+                options.highlight_cycles = highlight_cycles;
+            }
+            if !roots.is_empty() {
+                options.roots = roots;
+            }
+            if depth.is_some() {
+                options.depth = depth;
+            }
+            options.invert = invert;
+            options.node_table = node_table;
+            if let Some(path) = theme {
+                options.theme = Theme::load(&path)?;
+            }
+            Command::Graph
+        }
+        SubCommand::View { .. } => unreachable!(),
+        SubCommand::Callers { name, depth } => Command::Callers { name, depth },
+        SubCommand::Callees { name, depth } => Command::Callees { name, depth },
+        SubCommand::Paths { from, to, limit } => Command::Paths { from, to, limit },
+        SubCommand::Metrics { format, sort_by } => Command::Metrics {
+            format,
+            sort: sort_by,
+        },
+        SubCommand::DeadCode => Command::DeadCode,
+        SubCommand::Cycles => Command::Cycles,
+        SubCommand::Diff { base } => Command::Diff { base },
+    };
+
+    check(&options)?;
+    if global.watch || serve.is_some() {
+        return crate::watch(&directory, &command, &options, serve);
+    }
+    crate::run(directory, &command, &options)
+}
+
+/// Makes the options from the config (the defaults, the preset and the view) and `global`.
+/// The command line overrides the config.
+fn options(
+    config: Option<&Config>,
+    view: Option<&str>,
+    global: &GlobalArgs,
+) -> Result<Options, Box<dyn error::Error>> {
+    let mut options = Options::new();
+    if let Some(config) = config {
+        config.apply(global.preset.as_deref(), &mut options)?;
+        if let Some(view) = view {
+            config.apply_view(view, &mut options)?;
+        }
+    }
+
+    let flags = [
+        (global.print_data_type, &mut options.print_data_type),
+        (global.collapse_cycles, &mut options.collapse_cycles),
+        (
+            global.transitive_reduction,
+            &mut options.transitive_reduction,
+        ),
+        (global.prune_leaves, &mut options.prune_leaves),
+        (global.collapse_chains, &mut options.collapse_chains),
+    ];
+    for (flag, field) in flags {
+        if let Some(flag) = flag {
+            *field = flag;
+        }
+    }
+    if let Some(granularity) = global.granularity {
+        options.granularity = granularity;
+    }
+    if !global.exclude.is_empty() {
+        options.exclude = global.exclude.clone();
+    }
+    options.targets = Some(global.targets());
+    options.cache = !global.no_cache;
+    options.jobs = global.jobs.map(usize::from);
+    Ok(options)
+}
+
+/// Checks the options merged from the config and the command line.
+fn check(options: &Options) -> Result<(), Box<dyn error::Error>> {
+    if options.depth.is_some() && options.roots.is_empty() {
+        return Err("--depth needs the patterns of the start points, given with --root or `roots` of the config".into());
+    }
+    Ok(())
+}
+
+/// Writes to the file given with -o. Its extension decides the format, unless --format is given.
+fn set_output(options: &mut Options, global: &GlobalArgs) {
+    if global.output.is_some() {
+        options.format = None;
+        options.output = global.output.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::path::Path;
+
+    #[derive(Parser)]
+    struct Args {
+        #[command(flatten)]
+        global: GlobalArgs,
+        #[command(subcommand)]
+        command: SubCommand,
+    }
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from(["callgraph"].iter().chain(args)).unwrap()
+    }
+
+    fn config(text: &str) -> Config {
+        Config::parse(&text.parse().unwrap(), Path::new("")).unwrap()
+    }
+
+    #[test]
+    fn test_options() {
+        let config = config("prune_leaves = true\ncollapse_cycles = true\n");
+
+        let args = parse(&["--prune-leaves=false", "--transitive-reduction", "cycles"]);
+        let merged = options(Some(&config), None, &args.global).unwrap();
+        assert!(!merged.prune_leaves);
+        assert!(merged.collapse_cycles);
+        assert!(merged.transitive_reduction);
+        assert!(!merged.collapse_chains);
+
+        let args = parse(&["--lib", "--bin", "tool", "cycles"]);
+        let merged = options(None, None, &args.global).unwrap();
+        assert_eq!(
+            merged.targets,
+            Some(Targets {
+                lib: true,
+                bins: vec!["tool".to_string()],
+                all_targets: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_depth() {
+        // the roots can come from the config
+        let args = parse(&["graph", "--depth", "2"]);
+        let config = config("roots = [\"*::db::*\"]\n");
+        let mut merged = options(Some(&config), None, &args.global).unwrap();
+        merged.depth = Some(2);
+        assert!(check(&merged).is_ok());
+
+        merged.roots.clear();
+        assert!(check(&merged).is_err());
+    }
+}
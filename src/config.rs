@@ -0,0 +1,344 @@
+//! config is a module for the project configuration file.
+//!
+//! The configuration is read from `callgraph.toml` (or `.callgraph.toml`) in the project directory,
+//! or from the `[package.metadata.callgraph]` table of Cargo.toml.
+//!
+//! ```toml
+//! granularity = "module"
+//! format = "mermaid"
+//! exclude = ["std::*", "*::tests::*"]
+//! theme = "theme.toml"
+//!
+//! [presets.overview]
+//! granularity = "crate"
+//! transitive_reduction = true
+//!
+//! [views.storage]
+//! roots = ["*::db::*"]
+//! depth = 4
+//! granularity = "function"
+//! ```
+//!
+//! The top-level keys are the defaults, and a preset chosen with `--preset` is applied over them.
+//! A view is a graph rendered by `view <name>`, and is applied over the preset.
+//! Options given on the command line override all of them.
+use crate::{Format, Granularity, Options};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::error;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// File names searched in the project directory, in this order.
+const FILE_NAMES: [&str; 2] = ["callgraph.toml", ".callgraph.toml"];
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("invalid value for `{0}` in the config")]
+    InvalidValue(String),
+    #[error("unknown key `{0}` in the config")]
+    UnknownKey(String),
+    #[error("preset `{0}` not found in the config")]
+    PresetNotFound(String),
+    #[error("view `{0}` not found in the config")]
+    ViewNotFound(String),
+}
+
+/// Settings read from the configuration file.
+pub struct Config {
+    defaults: Settings,
+    presets: BTreeMap<String, Settings>,
+    views: BTreeMap<String, Settings>,
+}
+
+impl Config {
+    /// Finds the configuration of the project in `directory`. Returns None if there is none.
+    pub fn find(directory: &Path) -> Result<Option<Config>, Box<dyn error::Error>> {
+        for name in &FILE_NAMES {
+            let path = directory.join(name);
+            if path.is_file() {
+                return Config::load(&path).map(Some);
+            }
+        }
+
+        let manifest = directory.join("Cargo.toml");
+        if !manifest.is_file() {
+            return Ok(None);
+        }
+        let value: toml::Value = fs::read_to_string(&manifest)?.parse()?;
+        match value
+            .get("package")
+            .and_then(|x| x.get("metadata"))
+            .and_then(|x| x.get("callgraph"))
+        {
+            Some(table) => Ok(Some(Config::parse(table, directory)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Config, Box<dyn error::Error>> {
+        let value: toml::Value = fs::read_to_string(path)?.parse()?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Config::parse(&value, base)
+    }
+
+    /// `base` is the directory which relative paths in the config are based on.
+    pub fn parse(value: &toml::Value, base: &Path) -> Result<Config, Box<dyn error::Error>> {
+        Ok(Config {
+            defaults: Settings::parse(value, base, "")?,
+            presets: Config::parse_tables(value, base, "presets")?,
+            views: Config::parse_tables(value, base, "views")?,
+        })
+    }
+
+    /// Parses the named settings in the table `key`. ex. [presets.overview]
+    fn parse_tables(
+        value: &toml::Value,
+        base: &Path,
+        key: &str,
+    ) -> Result<BTreeMap<String, Settings>, Box<dyn error::Error>> {
+        let mut result = BTreeMap::new();
+        if let Some(tables) = value.get(key) {
+            let tables = tables
+                .as_table()
+                .ok_or_else(|| ConfigError::InvalidValue(key.to_string()))?;
+            for (name, table) in tables {
+                let settings = Settings::parse(table, base, &format!("{}.{}.", key, name))?;
+                result.insert(name.clone(), settings);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Applies the defaults and then the preset, if given.
+    pub fn apply(&self, preset: Option<&str>, options: &mut Options) -> Result<(), ConfigError> {
+        self.defaults.apply(options)?;
+        if let Some(name) = preset {
+            let settings = self
+                .presets
+                .get(name)
+                .ok_or_else(|| ConfigError::PresetNotFound(name.to_string()))?;
+            settings.apply(options)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the views, in sorted order.
+    pub fn view_names(&self) -> Vec<&str> {
+        self.views.keys().map(String::as_str).collect()
+    }
+
+    /// Applies the view over the options given by `apply`.
+    pub fn apply_view(&self, name: &str, options: &mut Options) -> Result<(), ConfigError> {
+        self.views
+            .get(name)
+            .ok_or_else(|| ConfigError::ViewNotFound(name.to_string()))?
+            .apply(options)
+    }
+}
+
+/// Options found in a table of the configuration. None if not given.
+#[derive(Default)]
+struct Settings {
+    print_data_type: Option<bool>,
+    granularity: Option<Granularity>,
+    format: Option<Format>,
+    highlight_cycles: Option<bool>,
+    collapse_cycles: Option<bool>,
+    transitive_reduction: Option<bool>,
+    prune_leaves: Option<bool>,
+    collapse_chains: Option<bool>,
+    roots: Option<Vec<String>>,
+    depth: Option<usize>,
+    exclude: Option<Vec<String>>,
+    /// theme table, read from the file if a path is given.
+    theme: Option<toml::Value>,
+}
+
+impl Settings {
+    /// `prefix` is the path of the table, used in error messages.
+    fn parse(
+        value: &toml::Value,
+        base: &Path,
+        prefix: &str,
+    ) -> Result<Settings, Box<dyn error::Error>> {
+        let table = value
+            .as_table()
+            .ok_or_else(|| ConfigError::InvalidValue(prefix.trim_end_matches('.').to_string()))?;
+        let mut settings = Settings::default();
+
+        for (key, value) in table {
+            let invalid = || ConfigError::InvalidValue(format!("{}{}", prefix, key));
+            let boolean = || value.as_bool().ok_or_else(invalid);
+            let strings = || -> Result<Vec<String>, ConfigError> {
+                value
+                    .as_array()
+                    .and_then(|x| x.iter().map(|x| x.as_str().map(String::from)).collect())
+                    .ok_or_else(invalid)
+            };
+
+            match key.as_str() {
+                "print_data_type" => settings.print_data_type = Some(boolean()?),
+                "granularity" => {
+                    settings.granularity = Some(value_enum(value).ok_or_else(invalid)?)
+                }
+                "format" => settings.format = Some(value_enum(value).ok_or_else(invalid)?),
+                "highlight_cycles" => settings.highlight_cycles = Some(boolean()?),
+                "collapse_cycles" => settings.collapse_cycles = Some(boolean()?),
+                "transitive_reduction" => settings.transitive_reduction = Some(boolean()?),
+                "prune_leaves" => settings.prune_leaves = Some(boolean()?),
+                "collapse_chains" => settings.collapse_chains = Some(boolean()?),
+                "roots" => settings.roots = Some(strings()?),
+                "depth" => {
+                    let depth = value.as_integer().filter(|x| *x >= 0).ok_or_else(invalid)?;
+                    settings.depth = Some(depth as usize)
+                }
+                "exclude" => settings.exclude = Some(strings()?),
+                "theme" => {
+                    settings.theme = Some(match value {
+                        toml::Value::String(path) => {
+                            fs::read_to_string(base.join(path))?.parse()?
+                        }
+                        toml::Value::Table(_) => value.clone(),
+                        _ => return Err(Box::new(invalid())),
+                    })
+                }
+                // tables of the named settings
+                "presets" | "views" if prefix.is_empty() => {}
+                _ => {
+                    return Err(Box::new(ConfigError::UnknownKey(format!(
+                        "{}{}",
+                        prefix, key
+                    ))))
+                }
+            }
+        }
+        Ok(settings)
+    }
+
+    fn apply(&self, options: &mut Options) -> Result<(), ConfigError> {
+        let flags = [
+            (self.print_data_type, &mut options.print_data_type),
+            (self.highlight_cycles, &mut options.highlight_cycles),
+            (self.collapse_cycles, &mut options.collapse_cycles),
+            (self.transitive_reduction, &mut options.transitive_reduction),
+            (self.prune_leaves, &mut options.prune_leaves),
+            (self.collapse_chains, &mut options.collapse_chains),
+        ];
+        for (value, field) in flags {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        if let Some(granularity) = self.granularity {
+            options.granularity = granularity;
+        }
+        if let Some(format) = self.format {
+            options.format = Some(format);
+        }
+        if let Some(roots) = &self.roots {
+            options.roots = roots.clone();
+        }
+        if let Some(depth) = self.depth {
+            options.depth = Some(depth);
+        }
+        if let Some(exclude) = &self.exclude {
+            options.exclude = exclude.clone();
+        }
+        if let Some(theme) = &self.theme {
+            options
+                .theme
+                .apply(theme)
+                .map_err(|_| ConfigError::InvalidValue("theme".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a value like "module" with the names used on the command line.
+fn value_enum<T: ValueEnum>(value: &toml::Value) -> Option<T> {
+    value.as_str().and_then(|x| T::from_str(x, true).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(text: &str) -> Result<Config, Box<dyn error::Error>> {
+        Config::parse(&text.parse().unwrap(), Path::new(""))
+    }
+
+    #[test]
+    fn test_apply() {
+        let config = config(
+            r#"
+            granularity = "module"
+            exclude = ["std::*"]
+            prune_leaves = true
+
+            [theme.shape]
+            function = "note"
+
+            [presets.overview]
+            granularity = "crate"
+            format = "mermaid"
+            "#,
+        )
+        .unwrap();
+
+        let mut options = Options::new();
+        config.apply(None, &mut options).unwrap();
+        assert_eq!(options.granularity, Granularity::Module);
+        assert_eq!(options.exclude, vec!["std::*".to_string()]);
+        assert!(options.prune_leaves);
+        assert_eq!(options.format, None);
+        assert_eq!(options.theme.function_shape, "note");
+
+        let mut options = Options::new();
+        config.apply(Some("overview"), &mut options).unwrap();
+        assert_eq!(options.granularity, Granularity::Crate);
+        assert_eq!(options.format, Some(Format::Mermaid));
+        assert!(options.prune_leaves);
+
+        assert!(config.apply(Some("nothing"), &mut Options::new()).is_err());
+    }
+
+    #[test]
+    fn test_apply_view() {
+        let config = config(
+            r#"
+            granularity = "module"
+
+            [views.storage]
+            roots = ["*::db::*"]
+            depth = 4
+            granularity = "function"
+
+            [views.overview]
+            granularity = "crate"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.view_names(), vec!["overview", "storage"]);
+
+        let mut options = Options::new();
+        config.apply(None, &mut options).unwrap();
+        config.apply_view("storage", &mut options).unwrap();
+        assert_eq!(options.granularity, Granularity::Function);
+        assert_eq!(options.roots, vec!["*::db::*".to_string()]);
+        assert_eq!(options.depth, Some(4));
+
+        assert!(config.apply_view("nothing", &mut options).is_err());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(config("granularity = \"modules\"").is_err());
+        assert!(config("prune_leaves = 1").is_err());
+        assert!(config("unknown = true").is_err());
+        assert!(config("[presets.a]\npresets = {}").is_err());
+        assert!(config("[views.a]\nviews = {}").is_err());
+        assert!(config("[views.a]\ndepth = -1").is_err());
+    }
+}
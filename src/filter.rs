@@ -1,25 +1,299 @@
-use crate::call_info::CallInfo;
+use crate::call_info::{CallEdge, CallInfo};
+use crate::graph::{CallGraph, GraphError};
+use std::collections::{HashMap, HashSet};
 
-pub fn filterling(callinfo_list: Vec<CallInfo>, options: &Options) -> Vec<CallInfo> {
-    let mut callinfo_list = callinfo_list;
-
-    // delete duplicate data
-    //let mut callinfo_list_no_dup: HashSet<CallInfo> = HashSet::new();
-    //for c in callinfo_list {
-    //    callinfo_list_no_dup.insert(c);
-    //}
-    callinfo_list.sort_by(|a, b| a.cmp(&b));
-    callinfo_list.dedup_by(|a, b| a == b);
+/// Filters the call sites (or `CallInfo`s) and merges them into edges.
+/// Fails if one of the roots of `depth` matches no function.
+pub fn filterling<T: Into<CallEdge>>(
+    callinfo_list: Vec<T>,
+    options: &Options,
+) -> Result<Vec<CallEdge>, GraphError> {
+    let mut callinfo_list: Vec<CallEdge> = callinfo_list.into_iter().map(Into::into).collect();
 
     // delete data type
     if !options.print_data_type {
+        callinfo_list
+            .retain(|x| !is_data_type(&x.callinfo.callee) && !is_data_type(&x.callinfo.caller));
+    }
+
+    // delete excluded functions
+    if !options.exclude.is_empty() {
+        let is_excluded = |name: &str| options.exclude.iter().any(|x| matches_pattern(x, name));
+        callinfo_list
+            .retain(|x| !is_excluded(&x.callinfo.callee) && !is_excluded(&x.callinfo.caller));
+    }
+
+    // collapse to the requested granularity. calls which can't be placed are dropped
+    if options.granularity != Granularity::Function {
+        let collapse = |name: &str| options.granularity.collapse(name, &options.scopes);
         callinfo_list = callinfo_list
             .into_iter()
-            .filter(|x| !is_data_type(&x.callee) && !is_data_type(&x.caller))
+            .filter_map(|x| {
+                let callinfo = CallInfo {
+                    callee: collapse(&x.callinfo.callee)?,
+                    caller: collapse(&x.callinfo.caller)?,
+                };
+                Some(CallEdge { callinfo, ..x })
+            })
+            // calls inside the same node are not interesting in a coarse graph
+            .filter(|x| x.callinfo.callee != x.callinfo.caller)
             .collect();
     }
 
-    callinfo_list
+    // merge duplicate data, counting how many calls were merged
+    let mut edges = merge_edges(callinfo_list);
+
+    if let Some(depth) = options.depth {
+        edges = limit_depth(edges, &options.roots, depth)?;
+    }
+    if options.collapse_cycles {
+        edges = collapse_cycles(edges);
+    }
+
+    // simplification passes
+    if options.transitive_reduction {
+        edges = transitive_reduction(edges);
+    }
+    if options.prune_leaves {
+        edges = prune_leaves(edges);
+    }
+    if options.collapse_chains {
+        edges = collapse_chains(edges);
+    }
+
+    Ok(edges)
+}
+
+/// Merges edges with the same caller and callee, summing their counts and collecting their locations.
+/// If only some of them are collapsed chains, the direct edge is kept.
+/// The kind of the first edge is kept. (function calls before method calls)
+fn merge_edges(edges: Vec<CallEdge>) -> Vec<CallEdge> {
+    let mut edges = edges;
+    edges.sort();
+
+    let mut result: Vec<CallEdge> = Vec::new();
+    for mut edge in edges {
+        match result.last_mut() {
+            Some(last) if last.callinfo == edge.callinfo => {
+                last.count += edge.count;
+                last.locations.append(&mut edge.locations);
+            }
+            _ => result.push(edge),
+        }
+    }
+    for edge in result.iter_mut() {
+        edge.locations.sort();
+        edge.locations.dedup();
+    }
+    result
+}
+
+/// Keeps only the edges from the functions within `depth` calls from the functions matching `roots`.
+/// A root matches like `CallGraph::find` (`name` or `*::name`), or as a pattern.
+fn limit_depth(
+    edges: Vec<CallEdge>,
+    roots: &[String],
+    depth: usize,
+) -> Result<Vec<CallEdge>, GraphError> {
+    let graph = CallGraph::new(&edges);
+    let mut nodes: Vec<usize> = Vec::new();
+    for root in roots {
+        let mut found = graph.find(root);
+        found.extend((0..graph.len()).filter(|node| matches_pattern(root, graph.name(*node))));
+        if found.is_empty() {
+            return Err(GraphError::NotFound(root.clone()));
+        }
+        nodes.append(&mut found);
+    }
+    nodes.sort_unstable();
+    nodes.dedup();
+    let distances = graph.distances(&nodes);
+
+    let is_near = |name: &str| {
+        graph
+            .node(name)
+            .and_then(|node| distances[node])
+            .is_some_and(|distance| distance < depth)
+    };
+    let keep: Vec<bool> = edges
+        .iter()
+        .map(|edge| is_near(&edge.callinfo.caller))
+        .collect();
+    Ok(edges
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(edge, keep)| if keep { Some(edge) } else { None })
+        .collect())
+}
+
+/// Replaces each strongly connected component with a single node, which makes the graph a DAG.
+/// The node is named by the common path of the members followed by their names.
+/// e.g. "krate::a::f" and "krate::a::g" are collapsed into "krate::a::f_g".
+fn collapse_cycles(edges: Vec<CallEdge>) -> Vec<CallEdge> {
+    let graph = CallGraph::new(&edges);
+    let components = graph.strongly_connected_components();
+    let ids = graph.component_ids(&components);
+
+    let component_names: Vec<String> = components
+        .iter()
+        .map(|component| {
+            let names: Vec<&str> = component.iter().map(|node| graph.name(*node)).collect();
+            collapsed_name(&names)
+        })
+        .collect();
+    let rename = |name: &str| match graph.node(name) {
+        Some(node) => component_names[ids[node]].clone(),
+        None => name.to_string(),
+    };
+
+    let edges = edges
+        .into_iter()
+        .filter_map(|edge| {
+            let callinfo = CallInfo {
+                callee: rename(&edge.callinfo.callee),
+                caller: rename(&edge.callinfo.caller),
+            };
+            if callinfo.callee == callinfo.caller {
+                return None;
+            }
+            Some(CallEdge { callinfo, ..edge })
+        })
+        .collect();
+    merge_edges(edges)
+}
+
+/// Drops A -> C when C is still reachable from A without it. (e.g. A -> B -> C)
+/// Edges are checked one by one against the already reduced graph, so the reachability is kept
+/// even in cycles.
+fn transitive_reduction(edges: Vec<CallEdge>) -> Vec<CallEdge> {
+    let mut callees: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in &edges {
+        callees
+            .entry(&edge.callinfo.caller)
+            .or_default()
+            .push(&edge.callinfo.callee);
+    }
+
+    let mut removed: HashSet<(&str, &str)> = HashSet::new();
+    for edge in &edges {
+        let caller = edge.callinfo.caller.as_str();
+        let callee = edge.callinfo.callee.as_str();
+        if caller == callee {
+            continue;
+        }
+
+        // search callee from the other callees of caller
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<&str> = callees[caller]
+            .iter()
+            .copied()
+            .filter(|x| *x != callee && !removed.contains(&(caller, *x)))
+            .collect();
+        let mut found = false;
+        while let Some(node) = stack.pop() {
+            if node == callee {
+                found = true;
+                break;
+            }
+            if !visited.insert(node) {
+                continue;
+            }
+            if let Some(next) = callees.get(node) {
+                stack.extend(next.iter().filter(|x| !removed.contains(&(node, **x))));
+            }
+        }
+        if found {
+            removed.insert((caller, callee));
+        }
+    }
+
+    let removed: HashSet<(String, String)> = removed
+        .into_iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+    edges
+        .into_iter()
+        .filter(|x| !removed.contains(&(x.callinfo.caller.clone(), x.callinfo.callee.clone())))
+        .collect()
+}
+
+/// Drops the nodes which call nothing and are called from only one caller.
+fn prune_leaves(edges: Vec<CallEdge>) -> Vec<CallEdge> {
+    let graph = CallGraph::new(&edges);
+    edges
+        .into_iter()
+        .filter(|edge| {
+            let callee = graph.node(&edge.callinfo.callee).unwrap();
+            !(graph.callees(callee).is_empty() && graph.callers(callee).len() == 1)
+        })
+        .collect()
+}
+
+/// Collapses linear chains A -> B -> C -> D, where B and C have exactly one caller and one callee,
+/// into a single edge A -> D which remembers B and C in `via`.
+fn collapse_chains(edges: Vec<CallEdge>) -> Vec<CallEdge> {
+    let graph = CallGraph::new(&edges);
+    let is_link = |name: &str| {
+        let node = graph.node(name).unwrap();
+        graph.callers(node).len() == 1
+            && graph.callees(node).len() == 1
+            && !graph.has_self_loop(node)
+    };
+    let mut next: HashMap<&str, &CallEdge> = HashMap::new();
+    for edge in &edges {
+        if is_link(&edge.callinfo.caller) {
+            next.insert(&edge.callinfo.caller, edge);
+        }
+    }
+
+    let mut result: Vec<CallEdge> = Vec::new();
+    let mut used: HashSet<&str> = HashSet::new();
+    for edge in &edges {
+        if is_link(&edge.callinfo.caller) {
+            continue;
+        }
+
+        let mut chain = edge.clone();
+        let mut link = edge.callinfo.callee.as_str();
+        while is_link(link) && used.insert(link) {
+            let following = next[link];
+            chain.via.push(link.to_string());
+            chain.via.extend(following.via.iter().cloned());
+            chain.callinfo.callee = following.callinfo.callee.clone();
+            link = &following.callinfo.callee;
+        }
+        result.push(chain);
+    }
+
+    // links which are not reachable from outside of the chain. (i.e. isolated cycles)
+    for edge in &edges {
+        if is_link(&edge.callinfo.caller) && !used.contains(edge.callinfo.caller.as_str()) {
+            result.push(edge.clone());
+        }
+    }
+
+    merge_edges(result)
+}
+
+fn collapsed_name(names: &[&str]) -> String {
+    if names.len() == 1 {
+        return names[0].to_string();
+    }
+
+    let paths: Vec<Vec<&str>> = names.iter().map(|x| x.split("::").collect()).collect();
+    let mut common = 0;
+    while paths
+        .iter()
+        .all(|path| path.len() > common + 1 && path[common] == paths[0][common])
+    {
+        common += 1;
+    }
+
+    let tails: Vec<String> = paths.iter().map(|path| path[common..].join("_")).collect();
+    let mut result: Vec<&str> = paths[0][..common].to_vec();
+    let tail = tails.join("_");
+    result.push(&tail);
+    result.join("::")
 }
 
 fn is_data_type(name: &str) -> bool {
@@ -32,21 +306,212 @@ fn is_data_type(name: &str) -> bool {
     false
 }
 
+fn is_type_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// Level at which nodes of the graph are merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Granularity {
+    /// one node per function or method
+    Function,
+    /// one node per type (free functions are merged into their module)
+    Type,
+    /// one node per module
+    Module,
+    /// one node per crate
+    Crate,
+}
+
+impl Granularity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::Function => "function",
+            Granularity::Type => "type",
+            Granularity::Module => "module",
+            Granularity::Crate => "crate",
+        }
+    }
+
+    /// Returns the node which the function `name` belongs to at this granularity,
+    /// or None if `scopes` can't tell. (e.g. a method whose receiver type is unknown)
+    /// e.g. "Conn::query" of module "db" collapses to "Conn" (Type), "db" (Module) and
+    /// the package name (Crate), and "std::collections::HashMap::new" to
+    /// "std::collections::HashMap", "std::collections" and "std".
+    pub fn collapse(&self, name: &str, scopes: &Scopes) -> Option<String> {
+        if *self == Granularity::Function {
+            return Some(name.to_string());
+        }
+        let scope = scopes.scope(name)?;
+        match self {
+            Granularity::Function => None,
+            // free functions are merged into their module
+            Granularity::Type => scope.ty.or(scope.module),
+            Granularity::Module => scope.module,
+            Granularity::Crate => scope.krate,
+        }
+    }
+}
+
+/// Crate, module and type which a function belongs to. None if unknown.
+struct Scope {
+    krate: Option<String>,
+    module: Option<String>,
+    ty: Option<String>,
+}
+
+/// Modules, types and functions defined in the analyzed crate, used to collapse the nodes.
+/// The other names are placed by their path. (e.g. "std::mem::swap")
+#[derive(Debug, Clone, Default)]
+pub struct Scopes {
+    package: String,
+    modules: HashSet<String>,
+    /// module of each type.
+    types: HashMap<String, String>,
+    /// module and impl type of each function.
+    functions: HashMap<String, (String, Option<String>)>,
+}
+
+impl Scopes {
+    /// `package` is the name of the crate, which is also the module of lib.rs and main.rs.
+    pub fn new(package: &str) -> Scopes {
+        Scopes {
+            package: package.to_string(),
+            ..Scopes::default()
+        }
+    }
+
+    pub fn add_type(&mut self, name: &str, module: &str) {
+        self.modules.insert(module.to_string());
+        self.types
+            .entry(name.to_string())
+            .or_insert_with(|| module.to_string());
+    }
+
+    /// `ty` is the type of the impl for methods.
+    pub fn add_function(&mut self, name: &str, module: &str, ty: Option<&str>) {
+        self.modules.insert(module.to_string());
+        self.functions
+            .entry(name.to_string())
+            .or_insert_with(|| (module.to_string(), ty.map(String::from)));
+    }
+
+    /// Returns true if `name`, a node at the type granularity, is a type and not the module
+    /// which its free functions are merged into.
+    pub fn is_type(&self, name: &str) -> bool {
+        self.types.contains_key(name) || name.rsplit("::").next().is_some_and(is_type_name)
+    }
+
+    fn scope(&self, name: &str) -> Option<Scope> {
+        let local = |module: &str, ty: Option<&str>| Scope {
+            krate: Some(self.package.clone()),
+            module: Some(module.to_string()),
+            ty: ty.map(String::from),
+        };
+        if let Some((module, ty)) = self.functions.get(name) {
+            return Some(local(module, ty.as_deref()));
+        }
+
+        let path: Vec<&str> = name.split("::").collect();
+        if path.len() == 1 {
+            return None;
+        }
+        let first = path[0];
+        if let Some(module) = self.types.get(first) {
+            // a method not found in the impls (e.g. derived or a default method of a trait)
+            return Some(local(module, Some(first)));
+        }
+        if first == self.package || self.modules.contains(first) {
+            // a function the analyzer could not resolve, called in the module
+            return Some(local(first, None));
+        }
+        if is_type_name(first) {
+            // a method of a type of another crate, known from the type of the receiver
+            return Some(Scope {
+                krate: None,
+                module: None,
+                ty: Some(first.to_string()),
+            });
+        }
+
+        // a path in another crate. the segments before the types are the module
+        let parent = &path[..path.len() - 1];
+        let modules = parent.iter().take_while(|x| !is_type_name(x)).count();
+        Some(Scope {
+            krate: Some(first.to_string()),
+            module: Some(parent[..modules].join("::")),
+            ty: if modules < parent.len() {
+                Some(parent.join("::"))
+            } else {
+                None
+            },
+        })
+    }
+}
+
 // print_data_type is that printing standard type ex) String, Vec, etc
 // TODO: std library no seigyo wo sitahouga yosasou
 pub struct Options {
     pub print_data_type: bool,
+    pub granularity: Granularity,
+    /// where the functions are defined, used by `granularity`.
+    pub scopes: Scopes,
+    pub collapse_cycles: bool,
+    pub transitive_reduction: bool,
+    pub prune_leaves: bool,
+    pub collapse_chains: bool,
+    /// patterns of the functions to drop. (see `matches_pattern`)
+    pub exclude: Vec<String>,
+    /// names or patterns of the start points of `depth`. (see `limit_depth`)
+    pub roots: Vec<String>,
+    /// keep only the functions within this number of calls from `roots`.
+    pub depth: Option<usize>,
 }
 
 impl Options {
     pub fn new(print_data_type: bool) -> Options {
-        Options { print_data_type }
+        Options {
+            print_data_type,
+            granularity: Granularity::Function,
+            scopes: Scopes::default(),
+            collapse_cycles: false,
+            transitive_reduction: false,
+            prune_leaves: false,
+            collapse_chains: false,
+            exclude: Vec::new(),
+            roots: Vec::new(),
+            depth: None,
+        }
+    }
+}
+
+/// Returns true if `name` matches `pattern`, where `*` matches any string including `::`.
+/// e.g. "std::*", "*::tests::*", "*::new"
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if name.len() < first.len() + last.len() || !name.starts_with(first) || !name.ends_with(last) {
+        return false;
     }
+    // the middle parts are matched from the left, as early as possible
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::call_info::CallKind;
+    use crate::test_util;
 
     #[test]
     fn test_filterling_print_data_type1() {
@@ -70,7 +535,7 @@ mod tests {
         ];
 
         let options = Options::new(false);
-        let callinfo_list = filterling(callinfo_list, &options);
+        let callinfo_list = filterling(callinfo_list, &options).unwrap();
 
         assert_eq!(callinfo_list.len(), 0);
     }
@@ -97,7 +562,7 @@ mod tests {
         ];
 
         let options = Options::new(true);
-        let callinfo_list = filterling(callinfo_list, &options);
+        let callinfo_list = filterling(callinfo_list, &options).unwrap();
 
         assert_eq!(callinfo_list.len(), 4);
     }
@@ -124,7 +589,7 @@ mod tests {
         ];
 
         let options = Options::new(true);
-        let callinfo_list = filterling(callinfo_list, &options);
+        let callinfo_list = filterling(callinfo_list, &options).unwrap();
 
         assert_eq!(callinfo_list.len(), 4);
 
@@ -152,8 +617,374 @@ mod tests {
         ];
 
         let options = Options::new(true);
-        let callinfo_list = filterling(callinfo_list, &options);
+        let callinfo_list = filterling(callinfo_list, &options).unwrap();
 
         assert_eq!(callinfo_list.len(), 4);
     }
+
+    #[test]
+    fn test_filterling_count() {
+        let callinfo_list = vec![
+            CallInfo {
+                caller: "MethodA".to_string(),
+                callee: "MethodB".to_string(),
+            },
+            CallInfo {
+                caller: "MethodA".to_string(),
+                callee: "MethodB".to_string(),
+            },
+            CallInfo {
+                caller: "MethodB".to_string(),
+                callee: "MethodC".to_string(),
+            },
+        ];
+
+        let options = Options::new(true);
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].count, 2);
+        assert_eq!(edges[1].count, 1);
+    }
+
+    fn scopes() -> Scopes {
+        let mut scopes = Scopes::new("krate");
+        scopes.add_type("Conn", "db");
+        scopes.add_function("krate::main", "krate", None);
+        scopes.add_function("Conn::open", "db", Some("Conn"));
+        scopes.add_function("db::helper", "db", None);
+        scopes.add_function("ui::show", "ui", None);
+        scopes
+    }
+
+    #[test]
+    fn test_granularity_collapse() {
+        let scopes = scopes();
+        let collapse = |level: Granularity, name: &str| level.collapse(name, &scopes);
+        assert_eq!(
+            collapse(Granularity::Function, "Conn::open").as_deref(),
+            Some("Conn::open")
+        );
+        assert_eq!(
+            collapse(Granularity::Type, "Conn::open").as_deref(),
+            Some("Conn")
+        );
+        assert_eq!(
+            collapse(Granularity::Module, "Conn::open").as_deref(),
+            Some("db")
+        );
+        assert_eq!(
+            collapse(Granularity::Crate, "Conn::open").as_deref(),
+            Some("krate")
+        );
+
+        // free functions are merged into their module at the type level
+        assert_eq!(
+            collapse(Granularity::Type, "db::helper").as_deref(),
+            Some("db")
+        );
+        assert_eq!(
+            collapse(Granularity::Crate, "krate::main").as_deref(),
+            Some("krate")
+        );
+        // a method of the crate which is not in the impls
+        assert_eq!(
+            collapse(Granularity::Module, "Conn::clone").as_deref(),
+            Some("db")
+        );
+        // a call the analyzer could not resolve
+        assert_eq!(
+            collapse(Granularity::Module, "ui::Vec::new").as_deref(),
+            Some("ui")
+        );
+
+        let name = "std::collections::HashMap::new";
+        assert_eq!(
+            collapse(Granularity::Type, name).as_deref(),
+            Some("std::collections::HashMap")
+        );
+        assert_eq!(
+            collapse(Granularity::Module, name).as_deref(),
+            Some("std::collections")
+        );
+        assert_eq!(collapse(Granularity::Crate, name).as_deref(), Some("std"));
+        assert_eq!(
+            collapse(Granularity::Type, "std::mem::swap").as_deref(),
+            Some("std::mem")
+        );
+
+        assert_eq!(
+            collapse(Granularity::Type, "Vec::push").as_deref(),
+            Some("Vec")
+        );
+        assert_eq!(collapse(Granularity::Module, "Vec::push"), None);
+        assert_eq!(collapse(Granularity::Module, "to_string"), None);
+
+        assert!(scopes.is_type("Conn"));
+        assert!(scopes.is_type("std::collections::HashMap"));
+        assert!(!scopes.is_type("db"));
+        assert!(!scopes.is_type("std::mem"));
+    }
+
+    #[test]
+    fn test_filterling_granularity() {
+        let callinfo_list = test_util::edges(&[
+            ("krate::main", "Conn::open"),
+            ("krate::main", "ui::show"),
+            ("krate::main", "query"),
+            ("ui::show", "db::helper"),
+            ("ui::show", "ui::draw"),
+            ("Conn::open", "db::helper"),
+        ]);
+
+        let mut options = Options::new(true);
+        options.granularity = Granularity::Module;
+        options.scopes = scopes();
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        assert_eq!(
+            pairs(&edges),
+            vec![("krate", "db"), ("ui", "db"), ("krate", "ui")]
+        );
+        assert_eq!(edges[0].count, 1);
+    }
+
+    /// Collapses the graph of a crate analyzed by the analyzer.
+    #[test]
+    fn test_filterling_granularity_analyzed() {
+        let dir = test_util::write_project(
+            "granularity",
+            &[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            (
+                "src/main.rs",
+                "mod db;\nmod ui;\nuse std::collections::HashMap;\n\
+                 fn main() {\n    let conn = db::Conn::open();\n    let rows = conn.query();\n\
+                 ui::show(&rows);\n    let _map: HashMap<u8, u8> = HashMap::new();\n}\n",
+            ),
+            (
+                "src/db.rs",
+                "pub struct Conn;\nimpl Conn {\n    pub fn open() -> Conn {\n        helper();\n\
+                 Conn\n    }\n    pub fn query(&self) -> Vec<String> {\n        Vec::new()\n    }\n}\n\
+                 pub fn helper() {\n    std::mem::drop(1);\n}\n",
+            ),
+            (
+                "src/ui.rs",
+                "use crate::db;\npub fn show(rows: &[String]) {\n    db::helper();\n\
+                 rows.to_vec();\n}\n",
+            ),
+            ],
+        );
+        let analysis = crate::analyzer::analyze(
+            &dir,
+            &crate::analyzer::Options::new(),
+            &mut crate::analyzer::Cache::new(),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let collapsed = |granularity: Granularity| {
+            let mut options = Options::new(true);
+            options.granularity = granularity;
+            options.scopes = analysis.scopes.clone();
+            let edges = filterling(analysis.calls.clone(), &options).unwrap();
+            let mut names: Vec<String> = pairs(&edges)
+                .into_iter()
+                .map(|(a, b)| format!("{} -> {}", a, b))
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            collapsed(Granularity::Type),
+            vec![
+                "Conn -> db",
+                "app -> Conn",
+                "app -> std::collections::HashMap",
+                "app -> ui",
+                "db -> std::mem",
+                "ui -> db",
+            ]
+        );
+        assert_eq!(
+            collapsed(Granularity::Module),
+            vec![
+                "app -> db",
+                "app -> std::collections",
+                "app -> ui",
+                "db -> std::mem",
+                "ui -> db",
+            ]
+        );
+        assert_eq!(collapsed(Granularity::Crate), vec!["app -> std"]);
+    }
+
+    #[test]
+    fn test_filterling_collapse_cycles() {
+        let callinfo_list = vec![
+            CallInfo {
+                caller: "krate::a::f".to_string(),
+                callee: "krate::a::g".to_string(),
+            },
+            CallInfo {
+                caller: "krate::a::g".to_string(),
+                callee: "krate::a::f".to_string(),
+            },
+            CallInfo {
+                caller: "krate::a::g".to_string(),
+                callee: "krate::b::h".to_string(),
+            },
+            CallInfo {
+                caller: "krate::a::f".to_string(),
+                callee: "krate::b::h".to_string(),
+            },
+        ];
+
+        let mut options = Options::new(true);
+        options.collapse_cycles = true;
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        let expect = vec![CallEdge {
+            callinfo: CallInfo {
+                caller: "krate::a::f_g".to_string(),
+                callee: "krate::b::h".to_string(),
+            },
+            count: 2,
+            via: Vec::new(),
+            kind: CallKind::Function,
+            locations: Vec::new(),
+        }];
+        assert_eq!(edges, expect);
+    }
+
+    fn pairs(edges: &[CallEdge]) -> Vec<(&str, &str)> {
+        edges
+            .iter()
+            .map(|x| (x.callinfo.caller.as_str(), x.callinfo.callee.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_filterling_transitive_reduction() {
+        let callinfo_list = test_util::edges(&[
+            ("a", "b"),
+            ("b", "c"),
+            ("a", "c"),
+            ("c", "d"),
+            ("d", "c"),
+            ("b", "d"),
+        ]);
+
+        let mut options = Options::new(true);
+        options.transitive_reduction = true;
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        assert_eq!(
+            pairs(&edges),
+            vec![("a", "b"), ("d", "c"), ("b", "d"), ("c", "d")]
+        );
+    }
+
+    #[test]
+    fn test_filterling_prune_leaves() {
+        let callinfo_list =
+            test_util::edges(&[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d"), ("d", "e")]);
+
+        let mut options = Options::new(true);
+        options.prune_leaves = true;
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        assert_eq!(
+            pairs(&edges),
+            vec![("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]
+        );
+    }
+
+    #[test]
+    fn test_filterling_collapse_chains() {
+        let callinfo_list = test_util::edges(&[
+            ("a", "b"),
+            ("b", "c"),
+            ("c", "d"),
+            ("a", "d"),
+            ("x", "y"),
+            ("y", "x"),
+        ]);
+
+        let mut options = Options::new(true);
+        options.collapse_chains = true;
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        assert_eq!(pairs(&edges), vec![("a", "d"), ("y", "x"), ("x", "y")]);
+        assert_eq!(edges[0].count, 2);
+        assert_eq!(edges[0].via, Vec::<String>::new());
+
+        let callinfo_list = test_util::edges(&[("a", "b"), ("b", "c"), ("c", "d"), ("e", "d")]);
+        let edges = filterling(callinfo_list, &options).unwrap();
+
+        assert_eq!(pairs(&edges), vec![("a", "d"), ("e", "d")]);
+        assert_eq!(edges[0].via, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("std::*", "std::mem::swap"));
+        assert!(!matches_pattern("std::*", "k::std::swap"));
+        assert!(matches_pattern("*::tests::*", "k::tests::helper"));
+        assert!(matches_pattern("*::db::*", "k::db::Pool::get"));
+        assert!(!matches_pattern("*::db::*", "k::dbx::get"));
+        assert!(matches_pattern("*new", "Type::new"));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(!matches_pattern("a*b*c", "ac"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("main", "main"));
+        assert!(!matches_pattern("main", "k::main"));
+    }
+
+    #[test]
+    fn test_filterling_exclude() {
+        let list = test_util::edges(&[
+            ("k::main", "k::f"),
+            ("k::f", "std::mem::swap"),
+            ("k::main", "k::g"),
+        ]);
+        let mut options = Options::new(true);
+        options.exclude = vec!["std::*".to_string(), "*::g".to_string()];
+        assert_eq!(
+            pairs(&filterling(list, &options).unwrap()),
+            vec![("k::main", "k::f")]
+        );
+    }
+
+    #[test]
+    fn test_filterling_depth() {
+        let list = test_util::edges(&[
+            ("k::main", "k::db::open"),
+            ("k::db::open", "k::db::connect"),
+            ("k::db::connect", "k::net::send"),
+            ("k::net::send", "k::net::write"),
+            ("k::main", "k::ui::draw"),
+        ]);
+        let mut options = Options::new(true);
+        options.roots = vec!["*::db::*".to_string()];
+        options.depth = Some(1);
+        assert_eq!(
+            pairs(&filterling(list.clone(), &options).unwrap()),
+            vec![
+                ("k::db::open", "k::db::connect"),
+                ("k::db::connect", "k::net::send")
+            ]
+        );
+
+        // a root also matches as the last segments of a name, like the name of `callees`
+        options.roots = vec!["net::send".to_string()];
+        assert_eq!(
+            pairs(&filterling(list.clone(), &options).unwrap()),
+            vec![("k::net::send", "k::net::write")]
+        );
+
+        options.roots = vec!["missing".to_string()];
+        let err = filterling(list, &options).unwrap_err();
+        assert_eq!(err.to_string(), "function not found: missing");
+    }
 }
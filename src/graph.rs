@@ -0,0 +1,791 @@
+//! graph is a module for algorithms over the call graph.
+use crate::call_info::{CallEdge, FunctionInfo, FunctionKind};
+use crate::filter::Granularity;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GraphError {
+    #[error("function not found: {0}")]
+    NotFound(String),
+}
+
+/// Index based view of a call graph.
+/// Nodes are numbered in the sorted order of their full names, so the results of
+/// the algorithms in this module do not depend on the order of the input edges.
+pub struct CallGraph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    callees: Vec<Vec<usize>>,
+    callers: Vec<Vec<usize>>,
+}
+
+impl CallGraph {
+    pub fn new(edges: &[CallEdge]) -> CallGraph {
+        let mut names: BTreeSet<&str> = BTreeSet::new();
+        for edge in edges {
+            names.insert(&edge.callinfo.caller);
+            names.insert(&edge.callinfo.callee);
+        }
+        let names: Vec<String> = names.into_iter().map(String::from).collect();
+        let index: HashMap<String, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
+
+        let mut callees = vec![Vec::new(); names.len()];
+        let mut callers = vec![Vec::new(); names.len()];
+        for edge in edges {
+            let caller = index[&edge.callinfo.caller];
+            let callee = index[&edge.callinfo.callee];
+            callees[caller].push(callee);
+            callers[callee].push(caller);
+        }
+        for list in callees.iter_mut().chain(callers.iter_mut()) {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        CallGraph {
+            names,
+            index,
+            callees,
+            callers,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, node: usize) -> &str {
+        &self.names[node]
+    }
+
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn callees(&self, node: usize) -> &[usize] {
+        &self.callees[node]
+    }
+
+    pub fn callers(&self, node: usize) -> &[usize] {
+        &self.callers[node]
+    }
+
+    /// Returns the nodes whose full name is `name` or ends with `::name`.
+    /// (e.g. "main", "Type::method")
+    pub fn find(&self, name: &str) -> Vec<usize> {
+        let suffix = format!("::{}", name);
+        (0..self.len())
+            .filter(|&node| self.names[node] == name || self.names[node].ends_with(&suffix))
+            .collect()
+    }
+
+    fn find_or_err(&self, name: &str) -> Result<Vec<usize>, GraphError> {
+        let nodes = self.find(name);
+        if nodes.is_empty() {
+            return Err(GraphError::NotFound(name.to_string()));
+        }
+        Ok(nodes)
+    }
+
+    /// Computes the strongly connected components with Tarjan's algorithm.
+    /// Each component is sorted, and the components are sorted by their first node.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            counter: 0,
+            indexes: vec![None; self.len()],
+            lowlinks: vec![0; self.len()],
+            stack: Vec::new(),
+            on_stack: vec![false; self.len()],
+            result: Vec::new(),
+        };
+        for node in 0..self.len() {
+            if tarjan.indexes[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        let mut result = tarjan.result;
+        for component in result.iter_mut() {
+            component.sort_unstable();
+        }
+        result.sort();
+        result
+    }
+
+    /// Returns the component number of each node.
+    pub fn component_ids(&self, components: &[Vec<usize>]) -> Vec<usize> {
+        let mut ids = vec![0; self.len()];
+        for (id, component) in components.iter().enumerate() {
+            for node in component {
+                ids[*node] = id;
+            }
+        }
+        ids
+    }
+
+    pub fn has_self_loop(&self, node: usize) -> bool {
+        self.callees[node].binary_search(&node).is_ok()
+    }
+
+    /// Returns the nodes reachable from `roots`, including the roots themselves.
+    pub fn reachable(&self, roots: &[usize]) -> Vec<bool> {
+        self.distances(roots).iter().map(Option::is_some).collect()
+    }
+
+    /// Returns the length of the shortest call chain from any of `roots` to each node.
+    pub fn distances(&self, roots: &[usize]) -> Vec<Option<usize>> {
+        self.breadth_first(roots, &self.callees)
+    }
+
+    /// Returns the length of the shortest call chain from each node to any of `roots`.
+    pub fn caller_distances(&self, roots: &[usize]) -> Vec<Option<usize>> {
+        self.breadth_first(roots, &self.callers)
+    }
+
+    fn breadth_first(&self, roots: &[usize], next: &[Vec<usize>]) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.len()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for &root in roots {
+            if distances[root].is_none() {
+                distances[root] = Some(0);
+                queue.push_back(root);
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            let distance = distances[node].map(|d| d + 1);
+            for &callee in &next[node] {
+                if distances[callee].is_none() {
+                    distances[callee] = distance;
+                    queue.push_back(callee);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Computes the betweenness centrality of each node with Brandes' algorithm.
+    pub fn betweenness(&self) -> Vec<f64> {
+        let mut result = vec![0.0; self.len()];
+        for source in 0..self.len() {
+            let mut stack: Vec<usize> = Vec::new();
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.len()];
+            let mut paths = vec![0.0; self.len()];
+            let mut distances: Vec<Option<usize>> = vec![None; self.len()];
+            paths[source] = 1.0;
+            distances[source] = Some(0);
+
+            let mut queue: VecDeque<usize> = VecDeque::new();
+            queue.push_back(source);
+            while let Some(node) = queue.pop_front() {
+                stack.push(node);
+                let distance = distances[node].map(|d| d + 1);
+                for &callee in self.callees(node) {
+                    if distances[callee].is_none() {
+                        distances[callee] = distance;
+                        queue.push_back(callee);
+                    }
+                    if distances[callee] == distance {
+                        paths[callee] += paths[node];
+                        predecessors[callee].push(node);
+                    }
+                }
+            }
+
+            let mut dependencies = vec![0.0; self.len()];
+            while let Some(node) = stack.pop() {
+                for &predecessor in &predecessors[node] {
+                    dependencies[predecessor] +=
+                        paths[predecessor] / paths[node] * (1.0 + dependencies[node]);
+                }
+                if node != source {
+                    result[node] += dependencies[node];
+                }
+            }
+        }
+        result
+    }
+}
+
+struct Tarjan<'a> {
+    graph: &'a CallGraph,
+    counter: usize,
+    indexes: Vec<Option<usize>>,
+    lowlinks: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    result: Vec<Vec<usize>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, node: usize) {
+        self.indexes[node] = Some(self.counter);
+        self.lowlinks[node] = self.counter;
+        self.counter += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &callee in self.graph.callees(node) {
+            match self.indexes[callee] {
+                None => {
+                    self.visit(callee);
+                    self.lowlinks[node] = self.lowlinks[node].min(self.lowlinks[callee]);
+                }
+                Some(index) if self.on_stack[callee] => {
+                    self.lowlinks[node] = self.lowlinks[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.lowlinks[node]) == self.indexes[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.result.push(component);
+        }
+    }
+}
+
+/// Cycles found in the call graph.
+#[derive(Debug, PartialEq)]
+pub struct CycleReport {
+    /// functions calling themselves.
+    pub direct_recursions: Vec<String>,
+    /// groups of functions calling each other.
+    pub mutual_recursions: Vec<Vec<String>>,
+    /// groups of modules depending on each other.
+    pub module_cycles: Vec<Vec<String>>,
+}
+
+/// Reports direct recursion, mutual recursion groups and module-level cycles.
+/// `module_edges` is the same graph collapsed to module granularity.
+pub fn find_cycles(edges: &[CallEdge], module_edges: &[CallEdge]) -> CycleReport {
+    let graph = CallGraph::new(edges);
+    let direct_recursions = (0..graph.len())
+        .filter(|node| graph.has_self_loop(*node))
+        .map(|node| graph.name(node).to_string())
+        .collect();
+
+    CycleReport {
+        direct_recursions,
+        mutual_recursions: cyclic_components(&graph),
+        module_cycles: cyclic_components(&CallGraph::new(module_edges)),
+    }
+}
+
+fn cyclic_components(graph: &CallGraph) -> Vec<Vec<String>> {
+    graph
+        .strongly_connected_components()
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|component| {
+            component
+                .into_iter()
+                .map(|node| graph.name(node).to_string())
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns true if the edge is a part of a cycle.
+pub fn cycle_edges(edges: &[CallEdge]) -> Vec<bool> {
+    let graph = CallGraph::new(edges);
+    let components = graph.strongly_connected_components();
+    let ids = graph.component_ids(&components);
+
+    edges
+        .iter()
+        .map(|edge| {
+            let caller = graph.index[&edge.callinfo.caller];
+            let callee = graph.index[&edge.callinfo.callee];
+            caller == callee || ids[caller] == ids[callee]
+        })
+        .collect()
+}
+
+/// Returns the callees (or the callers if `callers` is true) of the functions matching `name`,
+/// up to `depth` calls away, sorted by the distance and the name.
+pub fn find_related(
+    edges: &[CallEdge],
+    name: &str,
+    depth: usize,
+    callers: bool,
+) -> Result<Vec<String>, GraphError> {
+    let graph = CallGraph::new(edges);
+    let roots = graph.find_or_err(name)?;
+    let distances = if callers {
+        graph.caller_distances(&roots)
+    } else {
+        graph.distances(&roots)
+    };
+
+    let mut related: Vec<(usize, usize)> = distances
+        .into_iter()
+        .enumerate()
+        .filter_map(|(node, distance)| distance.map(|d| (d, node)))
+        .filter(|&(distance, _)| distance > 0 && distance <= depth)
+        .collect();
+    related.sort_unstable();
+    Ok(related
+        .into_iter()
+        .map(|(_, node)| graph.name(node).to_string())
+        .collect())
+}
+
+/// Returns up to `limit` call chains without repeated functions from `from` to `to`.
+pub fn find_paths(
+    edges: &[CallEdge],
+    from: &str,
+    to: &str,
+    limit: usize,
+) -> Result<Vec<Vec<String>>, GraphError> {
+    let graph = CallGraph::new(edges);
+    let sources = graph.find_or_err(from)?;
+    let targets = graph.find_or_err(to)?;
+    // only the nodes which can reach a target are visited
+    let can_reach = graph.caller_distances(&targets);
+    let mut is_target = vec![false; graph.len()];
+    for &target in &targets {
+        is_target[target] = true;
+    }
+
+    let mut paths: Vec<Vec<usize>> = Vec::new();
+    let mut path: Vec<usize> = Vec::new();
+    let mut on_path = vec![false; graph.len()];
+    for source in sources {
+        if can_reach[source].is_some() {
+            search_paths(
+                &graph,
+                source,
+                &is_target,
+                &can_reach,
+                limit,
+                &mut path,
+                &mut on_path,
+                &mut paths,
+            );
+        }
+    }
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            path.into_iter()
+                .map(|node| graph.name(node).to_string())
+                .collect()
+        })
+        .collect())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_paths(
+    graph: &CallGraph,
+    node: usize,
+    is_target: &[bool],
+    can_reach: &[Option<usize>],
+    limit: usize,
+    path: &mut Vec<usize>,
+    on_path: &mut Vec<bool>,
+    paths: &mut Vec<Vec<usize>>,
+) {
+    if paths.len() >= limit {
+        return;
+    }
+    path.push(node);
+    on_path[node] = true;
+    if is_target[node] && path.len() > 1 {
+        paths.push(path.clone());
+    } else {
+        for &callee in graph.callees(node) {
+            if !on_path[callee] && can_reach[callee].is_some() {
+                search_paths(
+                    graph, callee, is_target, can_reach, limit, path, on_path, paths,
+                );
+            }
+        }
+    }
+    on_path[node] = false;
+    path.pop();
+}
+
+/// Edges added and removed between two call graphs.
+#[derive(Debug, PartialEq)]
+pub struct EdgeDiff {
+    /// (caller, callee) found only in the new graph.
+    pub added: Vec<(String, String)>,
+    /// (caller, callee) found only in the base graph.
+    pub removed: Vec<(String, String)>,
+}
+
+/// Compares the edges of `base` and `edges`. The results are sorted by caller and callee.
+pub fn diff_edges(base: &[CallEdge], edges: &[CallEdge]) -> EdgeDiff {
+    let pairs = |edges: &[CallEdge]| -> BTreeSet<(String, String)> {
+        edges
+            .iter()
+            .map(|edge| (edge.callinfo.caller.clone(), edge.callinfo.callee.clone()))
+            .collect()
+    };
+    let base = pairs(base);
+    let new = pairs(edges);
+
+    EdgeDiff {
+        added: new.difference(&base).cloned().collect(),
+        removed: base.difference(&new).cloned().collect(),
+    }
+}
+
+/// Returns true if the function can be called from outside of the analyzed code.
+/// Trait methods are included because they are called through the trait,
+/// which the call graph can not follow.
+pub fn is_entry_point(function: &FunctionInfo) -> bool {
+    function.is_main
+        || function.is_exported
+        || function.is_test
+        || function.is_no_mangle
+        || function.is_extern
+        || function.kind == FunctionKind::TraitMethod
+}
+
+/// Functions which are not called from the code outside of the tests.
+#[derive(Debug, Default)]
+pub struct DeadCodeReport {
+    /// functions not reachable from any entry point.
+    pub unreachable: Vec<FunctionInfo>,
+    /// functions reachable only from the tests.
+    pub test_only: Vec<FunctionInfo>,
+}
+
+/// Finds the functions which are not reachable from the entry points other than the tests,
+/// and splits them by whether the tests reach them. Both lists are sorted by location.
+///
+/// Callee names are not always fully resolved by the analyzer (e.g. `Type::new` for `module::Type::new`),
+/// so a function is treated as reachable if its name and a reachable name match as a path suffix.
+/// An unresolved callee has to keep its qualifier to match, so a bare `new` does not match every
+/// `Type::new`.
+pub fn find_dead_code(edges: &[CallEdge], functions: &[FunctionInfo]) -> DeadCodeReport {
+    let graph = CallGraph::new(edges);
+    let roots = |tests: bool| -> Vec<usize> {
+        functions
+            .iter()
+            .filter(|function| is_entry_point(function) && function.is_test == tests)
+            .filter_map(|function| graph.node(&function.name))
+            .collect()
+    };
+    let live = reachable_functions(&graph, &roots(false), functions);
+    let tested = reachable_functions(&graph, &roots(true), functions);
+
+    let mut report = DeadCodeReport::default();
+    for function in functions {
+        if is_entry_point(function) || live.contains(function.name.as_str()) {
+            continue;
+        }
+        if tested.contains(function.name.as_str()) {
+            report.test_only.push(function.clone());
+        } else {
+            report.unreachable.push(function.clone());
+        }
+    }
+    for list in [&mut report.unreachable, &mut report.test_only] {
+        list.sort_by(|a, b| (&a.location, &a.name).cmp(&(&b.location, &b.name)));
+    }
+    report
+}
+
+/// Returns the names of `functions` reachable from `roots`. (see `find_dead_code`)
+fn reachable_functions<'a>(
+    graph: &CallGraph,
+    roots: &[usize],
+    functions: &'a [FunctionInfo],
+) -> HashSet<&'a str> {
+    let reachable = graph.reachable(roots);
+    let known: HashSet<&str> = functions.iter().map(|x| x.name.as_str()).collect();
+
+    let mut reachable_suffixes: HashSet<String> = HashSet::new();
+    for node in (0..graph.len()).filter(|node| reachable[*node]) {
+        reachable_suffixes.extend(path_suffixes(graph.name(node)));
+    }
+    // a reachable callee matching no function is unresolved, and matches by its qualified name
+    let is_unresolved_callee = |suffix: &str| {
+        suffix.contains("::")
+            && !known.contains(suffix)
+            && graph.node(suffix).is_some_and(|node| reachable[node])
+    };
+    let is_reachable = |name: &str| {
+        reachable_suffixes.contains(name)
+            || path_suffixes(name).any(|suffix| suffix != name && is_unresolved_callee(&suffix))
+    };
+    functions
+        .iter()
+        .map(|x| x.name.as_str())
+        .filter(|name| is_reachable(name))
+        .collect()
+}
+
+/// Metrics of a node in the call graph.
+#[derive(Debug, PartialEq)]
+pub struct NodeMetrics {
+    pub level: Granularity,
+    pub name: String,
+    /// number of distinct callers.
+    pub in_degree: usize,
+    /// number of distinct callees.
+    pub out_degree: usize,
+    /// number of nodes reachable from this node.
+    pub reach: usize,
+    pub betweenness: f64,
+    /// length of the shortest call chain from an entry point. (None if unreachable)
+    pub depth: Option<usize>,
+}
+
+/// Key to sort the metrics by. Numbers are sorted in descending order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetricsSortKey {
+    Name,
+    InDegree,
+    OutDegree,
+    Reach,
+    Betweenness,
+    Depth,
+}
+
+/// Computes the metrics of every node. `entry_points` are the names of the roots for the depth.
+pub fn compute_metrics(
+    edges: &[CallEdge],
+    entry_points: &[String],
+    level: Granularity,
+) -> Vec<NodeMetrics> {
+    let graph = CallGraph::new(edges);
+    let roots: Vec<usize> = entry_points
+        .iter()
+        .filter_map(|name| graph.node(name))
+        .collect();
+    let depths = graph.distances(&roots);
+    let betweenness = graph.betweenness();
+
+    (0..graph.len())
+        .map(|node| NodeMetrics {
+            level,
+            name: graph.name(node).to_string(),
+            in_degree: graph.callers(node).len(),
+            out_degree: graph.callees(node).len(),
+            reach: graph.reachable(&[node]).iter().filter(|x| **x).count() - 1,
+            betweenness: betweenness[node],
+            depth: depths[node],
+        })
+        .collect()
+}
+
+/// Sorts the metrics by `key`. Ties are ordered by level and name.
+pub fn sort_metrics(metrics: &mut [NodeMetrics], key: MetricsSortKey) {
+    metrics.sort_by(|a, b| {
+        let order = match key {
+            MetricsSortKey::Name => Ordering::Equal,
+            MetricsSortKey::InDegree => b.in_degree.cmp(&a.in_degree),
+            MetricsSortKey::OutDegree => b.out_degree.cmp(&a.out_degree),
+            MetricsSortKey::Reach => b.reach.cmp(&a.reach),
+            MetricsSortKey::Betweenness => b
+                .betweenness
+                .partial_cmp(&a.betweenness)
+                .unwrap_or(Ordering::Equal),
+            MetricsSortKey::Depth => b.depth.cmp(&a.depth),
+        };
+        order
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| (a.level as usize).cmp(&(b.level as usize)))
+    });
+}
+
+/// e.g. "a::b::c" -> "a::b::c", "b::c", "c"
+fn path_suffixes(name: &str) -> impl Iterator<Item = String> + '_ {
+    let path: Vec<&str> = name.split("::").collect();
+    (0..path.len()).map(move |i| path[i..].join("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{edges, function};
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let edges = edges(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d"), ("d", "d")]);
+        let graph = CallGraph::new(&edges);
+        let components: Vec<Vec<&str>> = graph
+            .strongly_connected_components()
+            .into_iter()
+            .map(|c| c.into_iter().map(|n| graph.name(n)).collect())
+            .collect();
+
+        assert_eq!(components, vec![vec!["a", "b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let function_edges = edges(&[
+            ("k::a::f", "k::a::f"),
+            ("k::a::g", "k::b::h"),
+            ("k::b::h", "k::a::g"),
+            ("k::b::h", "k::c::i"),
+        ]);
+        let module_edges = edges(&[("k::a", "k::b"), ("k::b", "k::a"), ("k::b", "k::c")]);
+        let report = find_cycles(&function_edges, &module_edges);
+
+        assert_eq!(report.direct_recursions, vec!["k::a::f"]);
+        assert_eq!(report.mutual_recursions, vec![vec!["k::a::g", "k::b::h"]]);
+        assert_eq!(report.module_cycles, vec![vec!["k::a", "k::b"]]);
+
+        assert_eq!(cycle_edges(&function_edges), vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn test_find_dead_code() {
+        let edges = edges(&[
+            ("k::main", "k::used"),
+            ("k::used", "k::Type::new"),
+            ("k::test_only", "k::helper"),
+            ("k::unused", "k::unused_too"),
+            ("k::used", "k::used::closure"),
+            // unresolved callees
+            ("k::used", "db::connect"),
+            ("k::used", "new"),
+        ]);
+        let mut closure = function("k::used::closure", 11);
+        closure.kind = FunctionKind::Closure;
+        let mut dead_closure = function("k::unused::closure", 12);
+        dead_closure.kind = FunctionKind::Closure;
+        let mut test_only = function("k::test_only", 5);
+        test_only.is_test = true;
+        let mut main = function("k::main", 1);
+        main.is_main = true;
+        let mut exported = function("k::exported", 8);
+        exported.is_public = true;
+        exported.is_exported = true;
+        let mut public = function("k::public", 9);
+        public.is_public = true;
+        let functions = vec![
+            main,
+            function("k::used", 2),
+            function("Type::new", 3),
+            function("k::helper", 4),
+            test_only,
+            function("k::unused_too", 7),
+            function("k::unused", 6),
+            exported,
+            public,
+            // not the main of a binary
+            function("k::tool::main", 10),
+            closure,
+            dead_closure,
+            function("k::db::connect", 13),
+            function("Other::new", 14),
+        ];
+
+        let report = find_dead_code(&edges, &functions);
+        let names =
+            |list: Vec<FunctionInfo>| -> Vec<String> { list.into_iter().map(|f| f.name).collect() };
+        assert_eq!(
+            names(report.unreachable),
+            vec![
+                "k::unused",
+                "k::unused_too",
+                "k::public",
+                "k::tool::main",
+                "k::unused::closure",
+                "Other::new"
+            ]
+        );
+        assert_eq!(names(report.test_only), vec!["k::helper"]);
+    }
+
+    #[test]
+    fn test_compute_metrics() {
+        let edges = edges(&[
+            ("main", "a"),
+            ("main", "b"),
+            ("a", "c"),
+            ("b", "c"),
+            ("c", "d"),
+        ]);
+        let mut metrics = compute_metrics(&edges, &["main".to_string()], Granularity::Function);
+        sort_metrics(&mut metrics, MetricsSortKey::Betweenness);
+
+        let c = &metrics[0];
+        assert_eq!(c.name, "c");
+        assert_eq!(c.in_degree, 2);
+        assert_eq!(c.out_degree, 1);
+        assert_eq!(c.reach, 1);
+        assert_eq!(c.betweenness, 3.0);
+        assert_eq!(c.depth, Some(2));
+
+        let main = metrics.iter().find(|m| m.name == "main").unwrap();
+        assert_eq!(main.reach, 4);
+        assert_eq!(main.depth, Some(0));
+        assert_eq!(
+            metrics.iter().find(|m| m.name == "a").unwrap().betweenness,
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_find_related() {
+        let edges = edges(&[
+            ("k::main", "k::a"),
+            ("k::a", "k::b"),
+            ("k::b", "k::c"),
+            ("k::main", "k::c"),
+        ]);
+        assert_eq!(
+            find_related(&edges, "main", 1, false).unwrap(),
+            vec!["k::a", "k::c"]
+        );
+        assert_eq!(
+            find_related(&edges, "c", 2, true).unwrap(),
+            vec!["k::b", "k::main", "k::a"]
+        );
+        assert!(find_related(&edges, "x", 1, false).is_err());
+    }
+
+    #[test]
+    fn test_find_paths() {
+        let edges = edges(&[
+            ("main", "a"),
+            ("main", "b"),
+            ("a", "c"),
+            ("b", "c"),
+            ("c", "a"),
+            ("b", "d"),
+        ]);
+        assert_eq!(
+            find_paths(&edges, "main", "c", 10).unwrap(),
+            vec![vec!["main", "a", "c"], vec!["main", "b", "c"]]
+        );
+        assert_eq!(find_paths(&edges, "main", "c", 1).unwrap().len(), 1);
+        assert!(find_paths(&edges, "d", "c", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_edges() {
+        let base = edges(&[("main", "a"), ("a", "b")]);
+        let new = edges(&[("main", "a"), ("a", "c")]);
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        assert_eq!(
+            diff_edges(&base, &new),
+            EdgeDiff {
+                added: vec![pair("a", "c")],
+                removed: vec![pair("a", "b")],
+            }
+        );
+    }
+}
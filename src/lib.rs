@@ -1,23 +1,291 @@
 mod analyzer;
 pub mod call_info;
+pub mod cli;
+mod config;
 mod filter;
+mod graph;
 mod output;
+#[cfg(test)]
+mod test_util;
+mod watch;
 
+pub use analyzer::{locate_package, Targets};
+pub use filter::Granularity;
+pub use graph::MetricsSortKey;
+pub use output::{Format, MetricsFormat, Theme};
+pub use watch::watch;
+
+use call_info::CallEdge;
+use filter::Scopes;
+use output::Destination;
 use std::error;
-use std::io;
+use std::io::{self, Write};
 use std::path::PathBuf;
 
-pub fn run(directory: PathBuf, print_data_type: bool) -> Result<(), Box<dyn error::Error>> {
-    // Analyze source code
-    let callinfo_list = analyzer::analyze(&directory)?;
+/// What to print.
+pub enum Command {
+    /// the call graph in `Options::format`.
+    Graph,
+    /// the functions which call `name`, up to `depth` calls away.
+    Callers { name: String, depth: usize },
+    /// the functions called by `name`, up to `depth` calls away.
+    Callees { name: String, depth: usize },
+    /// up to `limit` call chains from `from` to `to`.
+    Paths {
+        from: String,
+        to: String,
+        limit: usize,
+    },
+    /// fan-in, fan-out, reach, betweenness and depth of functions, types and modules.
+    Metrics {
+        format: MetricsFormat,
+        sort: MetricsSortKey,
+    },
+    /// the functions unreachable from the entry points.
+    DeadCode,
+    /// the cycles in the call graph.
+    Cycles,
+    /// the edges added and removed since the project in `base`.
+    Diff { base: PathBuf },
+}
+
+/// Options for a run given from the command line.
+pub struct Options {
+    /// print for data type. ex. Vec, String.
+    pub print_data_type: bool,
+    /// level at which nodes are merged.
+    pub granularity: Granularity,
+    /// output format of the graph. (inferred from the extension of `output` if None)
+    pub format: Option<Format>,
+    /// file to write the result to. (stdout if None)
+    pub output: Option<PathBuf>,
+    /// color the edges which are part of a cycle.
+    pub highlight_cycles: bool,
+    /// start points of the tree format, and patterns of the start points of `depth`.
+    pub roots: Vec<String>,
+    /// keep only the functions within this number of calls from `roots`.
+    pub depth: Option<usize>,
+    /// show the callers in the tree format.
+    pub invert: bool,
+    /// write the node table instead of the edge list in the CSV and TSV formats.
+    pub node_table: bool,
+    /// styles of the DOT format.
+    pub theme: Theme,
+    /// collapse each strongly connected component into a single node.
+    pub collapse_cycles: bool,
+    /// drop A -> C when A -> B -> C exists.
+    pub transitive_reduction: bool,
+    /// drop leaf nodes which have only one caller.
+    pub prune_leaves: bool,
+    /// collapse linear chains into one labeled edge.
+    pub collapse_chains: bool,
+    /// patterns of the functions to drop. ex. std::*, *::tests::*
+    pub exclude: Vec<String>,
+    /// analyze only the files of these targets. (every .rs file in the directory if None)
+    pub targets: Option<Targets>,
+    /// reuse the results of the files not modified since the last run, stored under target/.
+    pub cache: bool,
+    /// number of threads parsing the files. (the number of cores if None)
+    pub jobs: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options::new()
+    }
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            print_data_type: false,
+            granularity: Granularity::Function,
+            format: None,
+            output: None,
+            highlight_cycles: false,
+            roots: Vec::new(),
+            depth: None,
+            invert: false,
+            node_table: false,
+            theme: Theme::new(),
+            collapse_cycles: false,
+            transitive_reduction: false,
+            prune_leaves: false,
+            collapse_chains: false,
+            exclude: Vec::new(),
+            targets: None,
+            cache: true,
+            jobs: None,
+        }
+    }
+}
+
+pub fn run(
+    directory: PathBuf,
+    command: &Command,
+    options: &Options,
+) -> Result<(), Box<dyn error::Error>> {
+    run_cached(&directory, command, options, &mut analyzer::Cache::new())
+}
+
+/// Runs `command` reusing the analysis of the files not modified since the last run.
+pub(crate) fn run_cached(
+    directory: &PathBuf,
+    command: &Command,
+    options: &Options,
+    cache: &mut analyzer::Cache,
+) -> Result<(), Box<dyn error::Error>> {
+    let (destination, format) = Destination::new(options.output.as_deref(), options.format)?;
+    match destination {
+        Destination::Stdout => {
+            let mut f = io::BufWriter::new(io::stdout());
+            write_result(directory, command, options, format, &mut f, cache)?;
+            f.flush()?;
+        }
+        _ => {
+            let mut data: Vec<u8> = Vec::new();
+            write_result(directory, command, options, format, &mut data, cache)?;
+            destination.write(&data)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the result of `command`. `format` is the format of the graph.
+pub(crate) fn write_result<W: io::Write>(
+    directory: &PathBuf,
+    command: &Command,
+    options: &Options,
+    format: Format,
+    f: &mut W,
+    cache: &mut analyzer::Cache,
+) -> Result<(), Box<dyn error::Error>> {
+    match command {
+        Command::Graph => {
+            let analysis = analyzer::analyze(directory, &analyzer_options(options), cache)?;
+            let callinfo_list = filtered_edges(analysis.calls, &analysis.scopes, options)?;
+
+            // Create graph
+            let mut output_options = output::Options::new();
+            output_options.format = format;
+            output_options.granularity = options.granularity;
+            output_options.highlight_cycles = options.highlight_cycles;
+            output_options.roots = options.roots.clone();
+            output_options.invert = options.invert;
+            output_options.node_table = options.node_table;
+            output_options.theme = options.theme.clone();
+            output::render_to(callinfo_list, &analysis.functions, &output_options, f)?;
+        }
+        Command::Callers { name, depth } | Command::Callees { name, depth } => {
+            let analysis = analyzer::analyze(directory, &analyzer_options(options), cache)?;
+            let edges = filtered_edges(analysis.calls, &analysis.scopes, options)?;
+            let callers = matches!(command, Command::Callers { .. });
+            let names = graph::find_related(&edges, name, *depth, callers)?;
+            output::write_names(&names, f)?;
+        }
+        Command::Paths { from, to, limit } => {
+            let analysis = analyzer::analyze(directory, &analyzer_options(options), cache)?;
+            let edges = filtered_edges(analysis.calls, &analysis.scopes, options)?;
+            let paths = graph::find_paths(&edges, from, to, *limit)?;
+            output::write_paths(&paths, f)?;
+        }
+        Command::Metrics {
+            format: metrics_format,
+            sort,
+        } => {
+            let analysis = analyzer::analyze(directory, &analyzer_options(options), cache)?;
+            let entry_points: Vec<&str> = analysis
+                .functions
+                .iter()
+                .filter(|function| graph::is_entry_point(function))
+                .map(|function| function.name.as_str())
+                .collect();
 
-    // Filterling data
-    let filter_options = filter::Options::new(print_data_type);
-    let callinfo_list = filter::filterling(callinfo_list, &filter_options);
+            let mut metrics = Vec::new();
+            for level in &[
+                Granularity::Function,
+                Granularity::Type,
+                Granularity::Module,
+            ] {
+                let mut filter_options = filter::Options::new(options.print_data_type);
+                filter_options.granularity = *level;
+                filter_options.scopes = analysis.scopes.clone();
+                filter_options.exclude = options.exclude.clone();
+                let edges = filter::filterling(analysis.calls.clone(), &filter_options)?;
+                let roots: Vec<String> = entry_points
+                    .iter()
+                    .filter_map(|x| level.collapse(x, &analysis.scopes))
+                    .collect();
+                let mut level_metrics = graph::compute_metrics(&edges, &roots, *level);
+                // the modules of the free functions are reported at the module level
+                if *level == Granularity::Type {
+                    level_metrics.retain(|x| analysis.scopes.is_type(&x.name));
+                }
+                metrics.append(&mut level_metrics);
+            }
+            graph::sort_metrics(&mut metrics, *sort);
+            output::write_metrics(&metrics, *metrics_format, f)?;
+        }
+        Command::DeadCode => {
+            // the tests are roots of the reachability of the test-only functions
+            let mut analyzer_options = analyzer_options(options);
+            analyzer_options.include_tests = true;
+            let analysis = analyzer::analyze(directory, &analyzer_options, cache)?;
 
-    // Create graph
-    let mut f = io::BufWriter::new(io::stdout());
-    output::render_to(callinfo_list, &mut f)?;
+            let filter_options = filter::Options::new(options.print_data_type);
+            let edges = filter::filterling(analysis.calls, &filter_options)?;
+            let report = graph::find_dead_code(&edges, &analysis.functions);
+            output::write_dead_code_report(&report, f)?;
+        }
+        Command::Cycles => {
+            let analysis = analyzer::analyze(directory, &analyzer_options(options), cache)?;
+            let mut filter_options = filter::Options::new(options.print_data_type);
+            filter_options.exclude = options.exclude.clone();
+            let function_edges = filter::filterling(analysis.calls.clone(), &filter_options)?;
+            filter_options.granularity = Granularity::Module;
+            filter_options.scopes = analysis.scopes;
+            let module_edges = filter::filterling(analysis.calls, &filter_options)?;
+
+            let report = graph::find_cycles(&function_edges, &module_edges);
+            output::write_cycle_report(&report, f)?;
+        }
+        Command::Diff { base } => {
+            let base_analysis = analyzer::analyze(base, &analyzer_options(options), cache)?;
+            let analysis = analyzer::analyze(directory, &analyzer_options(options), cache)?;
+            let diff = graph::diff_edges(
+                &filtered_edges(base_analysis.calls, &base_analysis.scopes, options)?,
+                &filtered_edges(analysis.calls, &analysis.scopes, options)?,
+            );
+            output::write_diff(&diff, f)?;
+        }
+    }
 
     Ok(())
 }
+
+pub(crate) fn analyzer_options(options: &Options) -> analyzer::Options {
+    let mut analyzer_options = analyzer::Options::new();
+    analyzer_options.targets = options.targets.clone();
+    analyzer_options.disk_cache = options.cache;
+    analyzer_options.jobs = options.jobs;
+    analyzer_options
+}
+
+/// Filterling data with the granularity and the graph passes of `options`.
+fn filtered_edges(
+    calls: Vec<CallEdge>,
+    scopes: &Scopes,
+    options: &Options,
+) -> Result<Vec<CallEdge>, graph::GraphError> {
+    let mut filter_options = filter::Options::new(options.print_data_type);
+    filter_options.granularity = options.granularity;
+    filter_options.scopes = scopes.clone();
+    filter_options.collapse_cycles = options.collapse_cycles;
+    filter_options.transitive_reduction = options.transitive_reduction;
+    filter_options.prune_leaves = options.prune_leaves;
+    filter_options.collapse_chains = options.collapse_chains;
+    filter_options.exclude = options.exclude.clone();
+    filter_options.roots = options.roots.clone();
+    filter_options.depth = options.depth;
+    filter::filterling(calls, &filter_options)
+}
@@ -1,19 +1,22 @@
 use clap::Parser;
+use rust_gen_callgraph::cli::{self, GlobalArgs, SubCommand};
 use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// path for the analyze directory.
-    dirname: PathBuf,
-    /// print for data type. (default ignore) ex. Vec, String.
-    #[arg(long)]
-    print_data_type: bool,
+    #[arg(long, global = true, default_value = ".")]
+    path: PathBuf,
+    #[command(flatten)]
+    global: GlobalArgs,
+    #[command(subcommand)]
+    command: SubCommand,
 }
 
 fn main() {
     let args = Args::parse();
-    if let Err(err) = rust_gen_callgraph::run(args.dirname, args.print_data_type) {
+    if let Err(err) = cli::run(args.path, args.global, args.command) {
         eprintln!("{}", err);
         std::process::exit(2);
     }
@@ -1,60 +1,155 @@
+mod cluster_writer;
+mod destination;
 mod dot_writer;
-mod graph_creator;
+mod gexf_writer;
+mod graph_data;
+mod graphml_writer;
+mod html_writer;
+mod json_writer;
+mod mermaid_writer;
 mod module_tree;
+mod plantuml_writer;
+mod report;
+mod table_writer;
+mod theme;
+mod tree_writer;
 
-use crate::call_info::CallInfo;
-use graph_creator::ClusterDataType;
-use graph_creator::DotGraphBuilder;
+pub use destination::Destination;
+pub use report::{
+    write_cycle_report, write_dead_code_report, write_diff, write_metrics, write_names,
+    write_paths, MetricsFormat,
+};
+pub use theme::Theme;
+
+use crate::call_info::{CallEdge, FunctionInfo};
+use crate::filter::Granularity;
+use graph_data::GraphData;
 use module_tree::ClassPathTree;
 use std::io;
+use table_writer::Separator;
 
-pub fn render_to<W: io::Write>(callinfos: Vec<CallInfo>, output: &mut W) -> io::Result<()> {
-    let classpath_tree = make_classpath_tree(&callinfos);
-    //println!("{:?}", classpath_tree);
-
-    let create_dot_graph = DotGraphBuilder::new(callinfos);
+/// Output format of the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Dot,
+    Json,
+    /// Mermaid flowchart
+    Mermaid,
+    /// PlantUML component diagram
+    Plantuml,
+    Graphml,
+    Gexf,
+    /// self-contained interactive viewer
+    Html,
+    /// indented call tree for terminals
+    Tree,
+    /// edge list with comma separated values
+    Csv,
+    /// edge list with tab separated values
+    Tsv,
+}
 
-    let mut dot_writer = dot_writer::DotWriter::new();
+pub struct Options {
+    pub format: Format,
+    /// granularity of the nodes given to render_to.
+    pub granularity: Granularity,
+    pub highlight_cycles: bool,
+    /// start points of the tree format. (all uncalled functions if empty)
+    pub roots: Vec<String>,
+    /// show the callers instead of the callees in the tree format.
+    pub invert: bool,
+    /// write the node table instead of the edge list in the CSV and TSV formats.
+    pub node_table: bool,
+    /// styles of the DOT format.
+    pub theme: Theme,
+}
 
-    output.write_all(dot_writer::start().as_bytes())?;
-    //output.write_all(create_dot_graph.write_node_label().as_bytes())?;
-    for pathname in create_dot_graph.get_all_node() {
-        let node = dot_writer::node(&pathname);
-        output.write_all(node.as_bytes())?;
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            format: Format::Dot,
+            granularity: Granularity::Function,
+            highlight_cycles: false,
+            roots: Vec::new(),
+            invert: false,
+            node_table: false,
+            theme: Theme::new(),
+        }
     }
+}
 
-    classpath_tree.search_preorder(&create_dot_graph);
-
-    for cluster_data in &*create_dot_graph.borrow_result() {
-        let cluster = dot_writer.start_cluster(cluster_data.get_cluseter_name());
-        output.write_all(cluster.as_bytes())?;
-
-        for c in cluster_data.get_nodes() {
-            match c {
-                ClusterDataType::Single(single) => {
-                    let name = dot_writer::single_edge(single);
-                    output.write_all(name.as_bytes())?;
-                }
-                ClusterDataType::CallInfo(callinfo) => {
-                    let edge = dot_writer::edge(&callinfo.callee, &callinfo.caller);
-                    output.write_all(edge.as_bytes())?;
-                }
+/// Writes the graph in the format of `options`.
+/// `functions` are the definitions found by the analyzer, used for the metadata of the nodes.
+pub fn render_to<W: io::Write>(
+    callinfos: Vec<CallEdge>,
+    functions: &[FunctionInfo],
+    options: &Options,
+    output: &mut W,
+) -> io::Result<()> {
+    // every writer emits the edges in this order, and the nodes sorted by path
+    let mut callinfos = callinfos;
+    callinfos.sort_by(|a, b| {
+        (&a.callinfo.caller, &a.callinfo.callee).cmp(&(&b.callinfo.caller, &b.callinfo.callee))
+    });
+    match options.format {
+        Format::Dot => {
+            let classpath_tree = make_classpath_tree(&callinfos);
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            dot_writer::write_graph(
+                &graph,
+                &classpath_tree,
+                &options.theme,
+                options.highlight_cycles,
+                output,
+            )
+        }
+        Format::Json => {
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            json_writer::write_graph(&graph, options.granularity, output)
+        }
+        Format::Mermaid => {
+            let classpath_tree = make_classpath_tree(&callinfos);
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            mermaid_writer::write_graph(&graph, &classpath_tree, output)
+        }
+        Format::Plantuml => {
+            let classpath_tree = make_classpath_tree(&callinfos);
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            plantuml_writer::write_graph(&graph, &classpath_tree, output)
+        }
+        Format::Graphml => {
+            let classpath_tree = make_classpath_tree(&callinfos);
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            graphml_writer::write_graph(&graph, &classpath_tree, output)
+        }
+        Format::Gexf => {
+            let classpath_tree = make_classpath_tree(&callinfos);
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            gexf_writer::write_graph(&graph, &classpath_tree, output)
+        }
+        Format::Html => {
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            html_writer::write_graph(&graph, options.granularity, output)
+        }
+        Format::Csv | Format::Tsv => {
+            let separator = match options.format {
+                Format::Csv => Separator::Comma,
+                _ => Separator::Tab,
+            };
+            let graph = GraphData::new(callinfos, functions, options.granularity);
+            if options.node_table {
+                table_writer::write_nodes(&graph, separator, output)
+            } else {
+                table_writer::write_edges(&graph, separator, output)
             }
         }
-        output.write_all(dot_writer.end_cluster().as_bytes())?;
+        Format::Tree => tree_writer::write_tree(&callinfos, &options.roots, options.invert, output),
     }
-
-    create_dot_graph.borrow_mut_result().clear();
-
-    output.write_all(create_dot_graph.write_callinfo().as_bytes())?;
-    output.write_all(dot_writer::end().as_bytes())?;
-
-    Ok(())
 }
 
-fn make_classpath_tree(callinfo: &[CallInfo]) -> ClassPathTree {
+fn make_classpath_tree(callinfo: &[CallEdge]) -> ClassPathTree {
     let module_tree = ClassPathTree::new();
-    for c in callinfo.iter() {
+    for c in callinfo.iter().map(|x| &x.callinfo) {
         let mut fn_names_caller: Vec<&str> = c.caller.split("::").collect();
         fn_names_caller.pop().unwrap();
         module_tree.push(&fn_names_caller);
@@ -65,3 +160,68 @@ fn make_classpath_tree(callinfo: &[CallInfo]) -> ClassPathTree {
     }
     module_tree
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call_info::Location;
+    use crate::test_util;
+    use clap::ValueEnum;
+
+    fn edges() -> Vec<CallEdge> {
+        let mut edges = test_util::edges(&[
+            ("k::main", "k::db::open"),
+            ("k::main", "k::ui::draw"),
+            ("k::db::open", "k::db::Pool::get"),
+            ("k::db::Pool::get", "k::net::send"),
+            ("k::ui::draw", "k::net::send"),
+            ("k::net::send", "k::db::open"),
+            ("k::ui::draw", "std::mem::swap"),
+        ]);
+        for (line, edge) in edges.iter_mut().enumerate() {
+            edge.locations.push(Location {
+                file: "src/main.rs".into(),
+                line,
+            });
+        }
+        edges
+    }
+
+    fn functions() -> Vec<FunctionInfo> {
+        ["k::main", "k::db::open", "k::ui::draw", "k::net::send"]
+            .iter()
+            .enumerate()
+            .map(|(line, name)| test_util::function(name, line))
+            .collect()
+    }
+
+    fn render(edges: Vec<CallEdge>, functions: &[FunctionInfo], format: Format) -> Vec<u8> {
+        let mut options = Options::new();
+        options.format = format;
+        options.highlight_cycles = true;
+        let mut output = Vec::new();
+        render_to(edges, functions, &options, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_render_deterministic() {
+        for format in Format::value_variants() {
+            let expected = render(edges(), &functions(), *format);
+
+            // the hash maps of another run have other seeds
+            assert_eq!(render(edges(), &functions(), *format), expected);
+
+            let mut reversed_edges = edges();
+            reversed_edges.reverse();
+            let mut reversed_functions = functions();
+            reversed_functions.reverse();
+            assert_eq!(
+                render(reversed_edges, &reversed_functions, *format),
+                expected,
+                "{:?}",
+                format
+            );
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::graph_data::{GraphData, Node};
+use super::module_tree::{ClassPathTree, ClassPathTreeInterface};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+/// Syntax of the nested clusters of a format.
+pub trait ClusterSyntax {
+    fn start_cluster(&self, cluster_id: usize, name: &str, depth: usize) -> String;
+    fn end_cluster(&self, depth: usize) -> String;
+    fn node(&self, node: &Node, depth: usize) -> String;
+}
+
+/// Writes the nodes in nested clusters following `tree`.
+/// Each node is written once, inside its innermost cluster. Nodes without a module are written first.
+/// Clusters are numbered in the order of the preorder traversal.
+pub fn write_clusters(
+    graph: &GraphData,
+    tree: &ClassPathTree,
+    syntax: &dyn ClusterSyntax,
+) -> String {
+    let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+    for node in &graph.nodes {
+        children.entry(node.module()).or_default().push(node.id);
+    }
+
+    let writer = ClusterWriter {
+        graph,
+        children: &children,
+        syntax,
+        current_path: RefCell::new(Vec::new()),
+        cluster_counter: Cell::new(0),
+        result: RefCell::new(String::new()),
+    };
+    writer.write_nodes("", 1);
+    tree.search_preorder(&writer);
+
+    writer.result.into_inner()
+}
+
+struct ClusterWriter<'a> {
+    graph: &'a GraphData,
+    /// node ids grouped by the path of their parent.
+    children: &'a HashMap<&'a str, Vec<usize>>,
+    syntax: &'a dyn ClusterSyntax,
+    current_path: RefCell<Vec<String>>,
+    cluster_counter: Cell<usize>,
+    result: RefCell<String>,
+}
+
+impl<'a> ClusterWriter<'a> {
+    fn write_nodes(&self, parent: &str, depth: usize) {
+        if let Some(ids) = self.children.get(parent) {
+            let mut result = self.result.borrow_mut();
+            for id in ids {
+                result.push_str(&self.syntax.node(&self.graph.nodes[*id], depth));
+            }
+        }
+    }
+}
+
+impl<'a> ClassPathTreeInterface for ClusterWriter<'a> {
+    fn exec_search_before(&self, name: &str) -> bool {
+        let depth = {
+            let mut current_path = self.current_path.borrow_mut();
+            current_path.push(name.to_string());
+            current_path.len()
+        };
+        let counter = self.cluster_counter.get();
+        self.cluster_counter.set(counter + 1);
+        self.result
+            .borrow_mut()
+            .push_str(&self.syntax.start_cluster(counter, name, depth));
+
+        let path = self.current_path.borrow().join("::");
+        self.write_nodes(&path, depth + 1);
+        true
+    }
+
+    fn exec_search_after(&self, _name: &str) -> bool {
+        let depth = {
+            let mut current_path = self.current_path.borrow_mut();
+            let depth = current_path.len();
+            current_path.pop();
+            depth
+        };
+        self.result
+            .borrow_mut()
+            .push_str(&self.syntax.end_cluster(depth));
+        true
+    }
+}
+
+pub fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
@@ -0,0 +1,213 @@
+use super::Format;
+use std::error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DestinationError {
+    #[error("{0} output needs the dot format, but {1:?} was given")]
+    ImageNeedsDot(String, Format),
+    #[error("`dot` command not found. Install Graphviz to write {0} files, or write a .dot file instead")]
+    DotNotFound(String),
+    #[error("`dot` failed: {0}")]
+    DotFailed(String),
+    #[error("unknown extension .{0}. Give --format, or use one of {1}")]
+    UnknownExtension(String, String),
+}
+
+/// Where the result is written.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Destination {
+    Stdout,
+    File(PathBuf),
+    /// image rendered by the Graphviz `dot` command from the DOT output. (e.g. "svg")
+    Image(PathBuf, &'static str),
+}
+
+impl Destination {
+    /// Chooses the destination and the format of the graph.
+    /// Unless `format` is given, the format is inferred from the extension of `path`,
+    /// which has to be a known one. (DOT without an extension)
+    /// Images (.svg, .png, .pdf) are rendered from the DOT format.
+    pub fn new(
+        path: Option<&Path>,
+        format: Option<Format>,
+    ) -> Result<(Destination, Format), DestinationError> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok((Destination::Stdout, format.unwrap_or(Format::Dot))),
+        };
+        let extension = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if let Some(image) = image_format(&extension) {
+            return match format {
+                None | Some(Format::Dot) => {
+                    Ok((Destination::Image(path.into(), image), Format::Dot))
+                }
+                Some(format) => Err(DestinationError::ImageNeedsDot(extension, format)),
+            };
+        }
+        let format = match (format, format_from_extension(&extension)) {
+            (Some(format), _) | (None, Some(format)) => format,
+            (None, None) if extension.is_empty() => Format::Dot,
+            (None, None) => {
+                let supported: Vec<String> = FORMAT_EXTENSIONS
+                    .iter()
+                    .map(|(x, _)| *x)
+                    .chain(IMAGE_EXTENSIONS)
+                    .map(|x| format!(".{}", x))
+                    .collect();
+                return Err(DestinationError::UnknownExtension(
+                    extension,
+                    supported.join(", "),
+                ));
+            }
+        };
+        Ok((Destination::File(path.into()), format))
+    }
+
+    /// Writes `data` made for this destination.
+    pub fn write(&self, data: &[u8]) -> Result<(), Box<dyn error::Error>> {
+        match self {
+            Destination::Stdout => io::stdout().write_all(data)?,
+            Destination::File(path) => fs::write(path, data)?,
+            Destination::Image(path, image) => render_image(data, image, path)?,
+        }
+        Ok(())
+    }
+}
+
+impl Format {
+    /// Extension of the files written in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Dot => "dot",
+            Format::Json => "json",
+            Format::Mermaid => "mmd",
+            Format::Plantuml => "puml",
+            Format::Graphml => "graphml",
+            Format::Gexf => "gexf",
+            Format::Html => "html",
+            Format::Tree => "txt",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+        }
+    }
+}
+
+/// Extensions of the files written by the formats.
+const FORMAT_EXTENSIONS: [(&str, Format); 14] = [
+    ("dot", Format::Dot),
+    ("gv", Format::Dot),
+    ("json", Format::Json),
+    ("mmd", Format::Mermaid),
+    ("mermaid", Format::Mermaid),
+    ("puml", Format::Plantuml),
+    ("plantuml", Format::Plantuml),
+    ("graphml", Format::Graphml),
+    ("gexf", Format::Gexf),
+    ("html", Format::Html),
+    ("htm", Format::Html),
+    ("txt", Format::Tree),
+    ("csv", Format::Csv),
+    ("tsv", Format::Tsv),
+];
+
+/// Extensions of the images rendered by `dot`, which are also its `-T` formats.
+const IMAGE_EXTENSIONS: [&str; 3] = ["svg", "png", "pdf"];
+
+fn format_from_extension(extension: &str) -> Option<Format> {
+    FORMAT_EXTENSIONS
+        .iter()
+        .find(|(x, _)| *x == extension)
+        .map(|(_, format)| *format)
+}
+
+fn image_format(extension: &str) -> Option<&'static str> {
+    IMAGE_EXTENSIONS.iter().copied().find(|x| *x == extension)
+}
+
+/// Runs `dot -T<image> -o <path>` with the DOT source as the input.
+fn render_image(dot: &[u8], image: &str, path: &Path) -> Result<(), DestinationError> {
+    let mut child = Command::new("dot")
+        .arg(format!("-T{}", image))
+        .arg("-o")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => DestinationError::DotNotFound(format!(".{}", image)),
+            _ => DestinationError::DotFailed(err.to_string()),
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(dot)
+            .map_err(|err| DestinationError::DotFailed(err.to_string()))?;
+    }
+    let result = child
+        .wait_with_output()
+        .map_err(|err| DestinationError::DotFailed(err.to_string()))?;
+    if !result.status.success() {
+        let message = String::from_utf8_lossy(&result.stderr);
+        return Err(DestinationError::DotFailed(message.trim().to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let destination = |path: &str, format| {
+            Destination::new(Some(Path::new(path)), format).map_err(|err| err.to_string())
+        };
+
+        assert_eq!(
+            Destination::new(None, None).unwrap(),
+            (Destination::Stdout, Format::Dot)
+        );
+        assert_eq!(
+            destination("out/graph.json", None).unwrap(),
+            (Destination::File("out/graph.json".into()), Format::Json)
+        );
+        assert_eq!(
+            destination("graph.MMD", None).unwrap(),
+            (Destination::File("graph.MMD".into()), Format::Mermaid)
+        );
+        // the format given explicitly wins
+        assert_eq!(
+            destination("graph.txt", Some(Format::Csv)).unwrap(),
+            (Destination::File("graph.txt".into()), Format::Csv)
+        );
+        assert_eq!(
+            destination("graph", None).unwrap(),
+            (Destination::File("graph".into()), Format::Dot)
+        );
+        assert_eq!(
+            destination("graph.svg", None).unwrap(),
+            (Destination::Image("graph.svg".into(), "svg"), Format::Dot)
+        );
+        assert!(destination("graph.png", Some(Format::Json)).is_err());
+
+        // an unknown extension needs the format
+        let err = destination("graph.dat", None).unwrap_err();
+        assert!(err.starts_with("unknown extension .dat."));
+        assert!(err.contains(".dot, .gv, .json"));
+        assert!(err.ends_with(".svg, .png, .pdf"));
+        assert_eq!(
+            destination("graph.dat", Some(Format::Json)).unwrap(),
+            (Destination::File("graph.dat".into()), Format::Json)
+        );
+    }
+}
@@ -1,24 +1,119 @@
-pub struct DotWriter {
-    cluster_counter: usize,
+use super::cluster_writer::{self, indent, ClusterSyntax};
+use super::graph_data::{edge_label, short_name, GraphData, Node, NodeKind};
+use super::module_tree::ClassPathTree;
+use super::theme::Theme;
+use crate::call_info::{CallEdge, CallKind, FunctionKind};
+use crate::graph;
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+
+/// Writes the graph in the DOT format.
+/// Modules and types become nested clusters following `tree` (crate > module > type),
+/// each node is written once inside its innermost cluster and each edge once at the top level.
+/// Nodes are styled by their kind and top-level module with `theme`.
+/// If `highlight_cycles` is true, the edges which are part of a cycle are colored.
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    tree: &ClassPathTree,
+    theme: &Theme,
+    highlight_cycles: bool,
+    output: &mut W,
+) -> io::Result<()> {
+    let syntax = DotSyntax::new(graph, theme);
+    output.write_all(start().as_bytes())?;
+    output.write_all(cluster_writer::write_clusters(graph, tree, &syntax).as_bytes())?;
+
+    let in_cycle = if highlight_cycles {
+        graph::cycle_edges(&graph.edges)
+    } else {
+        vec![false; graph.edges.len()]
+    };
+    for (edge, in_cycle) in graph.edges.iter().zip(in_cycle) {
+        output.write_all(self::edge(edge, in_cycle, theme).as_bytes())?;
+    }
+    output.write_all(end().as_bytes())?;
+
+    Ok(())
 }
 
-impl DotWriter {
-    pub fn new() -> DotWriter {
-        DotWriter { cluster_counter: 0 }
+struct DotSyntax<'a> {
+    theme: &'a Theme,
+    /// fill color of each top-level module.
+    colors: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> DotSyntax<'a> {
+    fn new(graph: &'a GraphData, theme: &'a Theme) -> DotSyntax<'a> {
+        let modules: BTreeSet<&str> = graph.nodes.iter().map(|x| top_module(&x.path)).collect();
+        let colors = modules
+            .into_iter()
+            .zip(theme.palette.iter().cycle())
+            .map(|(module, color)| (module, color.as_str()))
+            .collect();
+        DotSyntax { theme, colors }
     }
 
-    pub fn start_cluster(&mut self, cluster_name: &str) -> String {
-        let mut result = "".to_string();
-        result += &format!("subgraph cluster_{} {{\n", self.cluster_counter);
-        result += &format!("label=\"{}\"\n", cluster_name);
+    fn node_attrs(&self, node: &Node) -> Vec<String> {
+        let theme = self.theme;
+        let mut attrs = vec![format!("label={}", quote(short_name(&node.path)))];
+        let shape = match node.kind {
+            NodeKind::Function(FunctionKind::Function) => &theme.function_shape,
+            NodeKind::Function(FunctionKind::Method) => &theme.method_shape,
+            NodeKind::Function(FunctionKind::TraitMethod) => &theme.trait_method_shape,
+            NodeKind::Function(FunctionKind::Closure) => &theme.closure_shape,
+            NodeKind::External if is_std(&node.path) => &theme.std_shape,
+            NodeKind::External => &theme.external_shape,
+            NodeKind::Type | NodeKind::Module | NodeKind::Crate => &theme.group_shape,
+        };
+        attrs.push(format!("shape={}", quote(shape)));
+
+        match node.kind {
+            NodeKind::External if is_std(&node.path) => {
+                attrs.push("fontcolor=\"gray40\"".to_string());
+            }
+            NodeKind::External => attrs.push("style=\"dashed\"".to_string()),
+            _ => {
+                attrs.push("style=\"filled\"".to_string());
+                let color = self.colors[top_module(&node.path)];
+                attrs.push(format!("fillcolor={}", quote(color)));
+            }
+        }
+
+        if let Some(function) = &node.definition {
+            if function.is_async {
+                attrs.push("peripheries=2".to_string());
+            }
+            if function.is_unsafe {
+                attrs.push(format!("color={}", quote(&theme.unsafe_color)));
+                attrs.push("penwidth=2".to_string());
+            }
+        }
+        attrs
+    }
+}
 
-        self.cluster_counter += 1;
+impl<'a> ClusterSyntax for DotSyntax<'a> {
+    fn start_cluster(&self, cluster_id: usize, name: &str, depth: usize) -> String {
+        format!(
+            "{}subgraph cluster_{} {{\n{}label={}\n",
+            indent(depth),
+            cluster_id,
+            indent(depth + 1),
+            quote(name)
+        )
+    }
 
-        result
+    fn end_cluster(&self, depth: usize) -> String {
+        format!("{}}}\n", indent(depth))
     }
 
-    pub fn end_cluster(&self) -> String {
-        "}\n".to_string()
+    fn node(&self, node: &Node, depth: usize) -> String {
+        format!(
+            "{}{} [{}]\n",
+            indent(depth),
+            quote(&node.path),
+            self.node_attrs(node).join(", ")
+        )
     }
 }
 
@@ -30,21 +125,161 @@ pub fn end() -> String {
     String::from("}\n")
 }
 
-pub fn node(name: &str) -> String {
-    let id = escape_for_id(name);
-    let binding: Vec<&str> = name.split("::").collect();
-    let label = binding.last().unwrap_or(&"");
-    format!("{} [label=\"{}\"]\n", id, label)
+/// The count of merged calls (if more than one) and the nodes of a collapsed chain are printed as the label.
+/// Edges that are part of a cycle are colored red when `in_cycle` is true,
+/// and calls on trait objects are drawn with the line style of the theme.
+pub fn edge(edge: &CallEdge, in_cycle: bool, theme: &Theme) -> String {
+    let mut attrs: Vec<String> = Vec::new();
+    if let Some(label) = edge_label(edge) {
+        attrs.push(format!("label={}", quote(&label)));
+    }
+    if edge.count > 1 {
+        attrs.push(format!("weight={}", edge.count));
+    }
+    if in_cycle {
+        attrs.push("color=red".to_string());
+    }
+    if edge.kind == CallKind::Dynamic {
+        attrs.push(format!("style={}", quote(&theme.dynamic_edge_style)));
+    }
+
+    let source = quote(&edge.callinfo.caller);
+    let dest = quote(&edge.callinfo.callee);
+    if attrs.is_empty() {
+        format!("{} -> {}\n", source, dest)
+    } else {
+        format!("{} -> {} [{}]\n", source, dest, attrs.join(", "))
+    }
+}
+
+/// e.g. "module" for "module::Type::func"
+fn top_module(path: &str) -> &str {
+    path.split("::").next().unwrap_or("")
+}
+
+fn is_std(path: &str) -> bool {
+    matches!(top_module(path), "std" | "core" | "alloc")
 }
 
-pub fn edge(source: &str, dest: &str) -> String {
-    format!("{} -> {}\n", escape_for_id(source), escape_for_id(dest))
+/// Returns a double-quoted DOT string, which is valid as an ID and as a label for any text.
+/// Quoted IDs can't collide with keywords like `graph` or `node`.
+fn quote(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            // a backslash would start an escape sequence like \l in labels
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => {}
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
 }
 
-pub fn single_edge(source: &str) -> String {
-    format!("{}\n", escape_for_id(source))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Granularity;
+    use crate::test_util;
+
+    #[test]
+    fn test_write_graph() {
+        let edges = test_util::edges(&[
+            ("k::a::f", "k::a::T::g"),
+            ("k::a::f", "k::b::h"),
+            ("k::a::T::g", "k::a::f"),
+            ("main", "k::a::f"),
+        ]);
+
+        let tree = crate::output::make_classpath_tree(&edges);
+        let graph = GraphData::new(edges, &[], Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, &tree, &Theme::new(), true, &mut result).unwrap();
+
+        let expect = r#"digraph G {
+ rankdir=LR;
+    "main" [label="main", shape="component", style="dashed"]
+    subgraph cluster_0 {
+        label="k"
+        subgraph cluster_1 {
+            label="a"
+            "k::a::f" [label="f", shape="component", style="dashed"]
+            subgraph cluster_2 {
+                label="T"
+                "k::a::T::g" [label="g", shape="component", style="dashed"]
+            }
+        }
+        subgraph cluster_3 {
+            label="b"
+            "k::b::h" [label="h", shape="component", style="dashed"]
+        }
+    }
+"k::a::f" -> "k::a::T::g" [color=red]
+"k::a::f" -> "k::b::h"
+"k::a::T::g" -> "k::a::f" [color=red]
+"main" -> "k::a::f"
 }
+"#;
+        assert_eq!(String::from_utf8(result).unwrap(), expect);
+    }
 
-fn escape_for_id(name: &str) -> String {
-    name.replace([':', '-'], "_")
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("graph"), r#""graph""#);
+        assert_eq!(quote("1st"), r#""1st""#);
+        assert_eq!(quote("Vec<&'a str>::push"), r#""Vec<&'a str>::push""#);
+        assert_eq!(quote(r#"say "hi"\n"#), r#""say \"hi\"\\n""#);
+        assert_eq!(quote("a\nb"), r#""a\nb""#);
+    }
+
+    #[test]
+    fn test_style() {
+        let mut async_function = test_util::function("a::f", 1);
+        async_function.is_async = true;
+        let mut unsafe_method = test_util::function("b::T::g", 2);
+        unsafe_method.kind = FunctionKind::TraitMethod;
+        unsafe_method.is_unsafe = true;
+        let mut closure = test_util::function("a::f::c", 3);
+        closure.kind = FunctionKind::Closure;
+        let functions = vec![async_function, unsafe_method, closure];
+        let mut edges = test_util::edges(&[
+            ("a::f", "b::T::g"),
+            ("a::f", "std::mem::swap"),
+            ("a::f", "a::f::c"),
+            ("a::f", "x::y"),
+        ]);
+        edges[0].kind = CallKind::Dynamic;
+        let graph = GraphData::new(edges, &functions, Granularity::Function);
+        let theme = Theme::new();
+        let syntax = DotSyntax::new(&graph, &theme);
+
+        assert_eq!(
+            syntax.node(&graph.nodes[0], 0),
+            "\"a::f\" [label=\"f\", shape=\"box\", style=\"filled\", fillcolor=\"#cfe2f3\", peripheries=2]\n"
+        );
+        assert_eq!(
+            syntax.node(&graph.nodes[2], 0),
+            "\"b::T::g\" [label=\"g\", shape=\"hexagon\", style=\"filled\", fillcolor=\"#d9ead3\", color=\"red\", penwidth=2]\n"
+        );
+        assert_eq!(
+            syntax.node(&graph.nodes[1], 0),
+            "\"a::f::c\" [label=\"c\", shape=\"octagon\", style=\"filled\", fillcolor=\"#cfe2f3\"]\n"
+        );
+        assert_eq!(
+            syntax.node(&graph.nodes[3], 0),
+            "\"std::mem::swap\" [label=\"swap\", shape=\"plaintext\", fontcolor=\"gray40\"]\n"
+        );
+        assert_eq!(
+            syntax.node(&graph.nodes[4], 0),
+            "\"x::y\" [label=\"y\", shape=\"component\", style=\"dashed\"]\n"
+        );
+        assert_eq!(
+            edge(&graph.edges[0], false, &theme),
+            "\"a::f\" -> \"b::T::g\" [style=\"dashed\"]\n"
+        );
+    }
 }
@@ -0,0 +1,144 @@
+use super::cluster_writer::{self, indent, ClusterSyntax};
+use super::graph_data::{short_name, GraphData, Node};
+use super::graphml_writer::escape;
+use super::module_tree::ClassPathTree;
+use std::io;
+
+/// Writes the graph in GEXF 1.2. (for Gephi)
+/// Modules and types become nodes containing their members as nested nodes, following `tree`.
+/// Nodes have the attributes path, module, kind, visibility and location,
+/// and edges have the number of calls as weight.
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    tree: &ClassPathTree,
+    output: &mut W,
+) -> io::Result<()> {
+    output.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.2" version="1.2">
+  <graph defaultedgetype="directed">
+    <attributes class="node">
+      <attribute id="path" title="path" type="string"/>
+      <attribute id="module" title="module" type="string"/>
+      <attribute id="kind" title="kind" type="string"/>
+      <attribute id="visibility" title="visibility" type="string"/>
+      <attribute id="location" title="location" type="string"/>
+    </attributes>
+    <nodes>
+"#,
+    )?;
+    output.write_all(cluster_writer::write_clusters(graph, tree, &GexfSyntax).as_bytes())?;
+    output.write_all(b"    </nodes>\n    <edges>\n")?;
+    for (i, edge) in graph.edges.iter().enumerate() {
+        writeln!(
+            output,
+            "      <edge id=\"e{}\" source=\"n{}\" target=\"n{}\" weight=\"{}\"/>",
+            i,
+            graph.node_id(&edge.callinfo.caller),
+            graph.node_id(&edge.callinfo.callee),
+            edge.count
+        )?;
+    }
+    output.write_all(b"    </edges>\n  </graph>\n</gexf>\n")?;
+
+    Ok(())
+}
+
+struct GexfSyntax;
+
+impl ClusterSyntax for GexfSyntax {
+    fn start_cluster(&self, cluster_id: usize, name: &str, depth: usize) -> String {
+        let indent = format!("{}  ", indent(depth));
+        format!(
+            "{}<node id=\"c{}\" label=\"{}\">\n{}  <nodes>\n",
+            indent,
+            cluster_id,
+            escape(name),
+            indent
+        )
+    }
+
+    fn end_cluster(&self, depth: usize) -> String {
+        let indent = format!("{}  ", indent(depth));
+        format!("{}  </nodes>\n{}</node>\n", indent, indent)
+    }
+
+    fn node(&self, node: &Node, depth: usize) -> String {
+        let indent = format!("{}  ", indent(depth));
+        let mut result = format!(
+            "{}<node id=\"n{}\" label=\"{}\">\n{}  <attvalues>\n",
+            indent,
+            node.id,
+            escape(short_name(&node.path)),
+            indent
+        );
+        let location = node.location();
+        let values = [
+            ("path", node.path.as_str()),
+            ("module", node.module()),
+            ("kind", node.kind.as_str()),
+            ("visibility", node.visibility()),
+            ("location", &location),
+        ];
+        for (key, value) in values.iter() {
+            if !value.is_empty() {
+                result += &format!(
+                    "{}    <attvalue for=\"{}\" value=\"{}\"/>\n",
+                    indent,
+                    key,
+                    escape(value)
+                );
+            }
+        }
+        result += &format!("{}  </attvalues>\n{}</node>\n", indent, indent);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Granularity;
+    use crate::test_util;
+
+    #[test]
+    fn test_write_graph() {
+        let mut edges = test_util::edges(&[("k::f", "k::g")]);
+        edges[0].count = 3;
+        let tree = crate::output::make_classpath_tree(&edges);
+        let graph = GraphData::new(edges, &[], Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, &tree, &mut result).unwrap();
+        let result = String::from_utf8(result).unwrap();
+
+        let body = &result[result.find("    <nodes>").unwrap()..];
+        let expect = r#"    <nodes>
+      <node id="c0" label="k">
+        <nodes>
+          <node id="n0" label="f">
+            <attvalues>
+              <attvalue for="path" value="k::f"/>
+              <attvalue for="module" value="k"/>
+              <attvalue for="kind" value="external"/>
+            </attvalues>
+          </node>
+          <node id="n1" label="g">
+            <attvalues>
+              <attvalue for="path" value="k::g"/>
+              <attvalue for="module" value="k"/>
+              <attvalue for="kind" value="external"/>
+            </attvalues>
+          </node>
+        </nodes>
+      </node>
+    </nodes>
+    <edges>
+      <edge id="e0" source="n0" target="n1" weight="3"/>
+    </edges>
+  </graph>
+</gexf>
+"#;
+        assert_eq!(body, expect);
+    }
+}
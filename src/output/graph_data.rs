@@ -0,0 +1,155 @@
+use crate::call_info::{CallEdge, FunctionInfo, FunctionKind};
+use crate::filter::Granularity;
+use std::collections::{BTreeSet, HashMap};
+
+/// Kind of a node in the output graph.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum NodeKind {
+    Function(FunctionKind),
+    /// function whose definition was not found in the project.
+    External,
+    Type,
+    Module,
+    Crate,
+}
+
+impl NodeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeKind::Function(kind) => kind.as_str(),
+            NodeKind::External => "external",
+            NodeKind::Type => "type",
+            NodeKind::Module => "module",
+            NodeKind::Crate => "crate",
+        }
+    }
+}
+
+pub struct Node {
+    pub id: usize,
+    pub path: String,
+    pub kind: NodeKind,
+    /// definition of the function. (only for functions found in the project)
+    pub definition: Option<FunctionInfo>,
+}
+
+impl Node {
+    /// e.g. "crate::module" for "crate::module::func"
+    pub fn module(&self) -> &str {
+        match self.path.rfind("::") {
+            Some(idx) => &self.path[..idx],
+            None => "",
+        }
+    }
+
+    /// "public", "private" or "" if the definition is unknown.
+    pub fn visibility(&self) -> &'static str {
+        match &self.definition {
+            Some(function) if function.is_public => "public",
+            Some(_) => "private",
+            None => "",
+        }
+    }
+
+    /// "file:line" of the definition or "" if unknown.
+    pub fn location(&self) -> String {
+        match &self.definition {
+            Some(function) => function.location.to_string(),
+            None => String::new(),
+        }
+    }
+}
+
+/// Nodes and edges of the graph with the metadata for the writers.
+/// Nodes are sorted by path and numbered in that order.
+pub struct GraphData {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<CallEdge>,
+    index: HashMap<String, usize>,
+}
+
+impl GraphData {
+    pub fn new(
+        edges: Vec<CallEdge>,
+        functions: &[FunctionInfo],
+        granularity: Granularity,
+    ) -> GraphData {
+        let mut definitions: HashMap<&str, &FunctionInfo> = HashMap::new();
+        for function in functions {
+            let definition = definitions.entry(&function.name).or_insert(function);
+            // keep the first one when the same name is defined several times
+            if function.location < definition.location {
+                *definition = function;
+            }
+        }
+
+        let mut paths: BTreeSet<&str> = BTreeSet::new();
+        for edge in &edges {
+            paths.insert(&edge.callinfo.caller);
+            paths.insert(&edge.callinfo.callee);
+        }
+
+        let nodes: Vec<Node> = paths
+            .into_iter()
+            .enumerate()
+            .map(|(id, path)| {
+                let definition = match granularity {
+                    Granularity::Function => definitions.get(path).map(|x| (*x).clone()),
+                    _ => None,
+                };
+                let kind = match granularity {
+                    Granularity::Function => match &definition {
+                        Some(definition) => NodeKind::Function(definition.kind),
+                        None => NodeKind::External,
+                    },
+                    Granularity::Type => NodeKind::Type,
+                    Granularity::Module => NodeKind::Module,
+                    Granularity::Crate => NodeKind::Crate,
+                };
+                Node {
+                    id,
+                    path: path.to_string(),
+                    kind,
+                    definition,
+                }
+            })
+            .collect();
+        let index = nodes
+            .iter()
+            .map(|node| (node.path.clone(), node.id))
+            .collect();
+
+        GraphData {
+            nodes,
+            edges,
+            index,
+        }
+    }
+
+    pub fn node_id(&self, path: &str) -> usize {
+        self.index[path]
+    }
+}
+
+/// e.g. "crate::module::func" -> "func"
+pub fn short_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or("")
+}
+
+/// Returns the label of the edge, made of the count (if more than one) and the collapsed chain.
+/// e.g. "2: b > c"
+pub fn edge_label(edge: &CallEdge) -> Option<String> {
+    let mut labels: Vec<String> = Vec::new();
+    if edge.count > 1 {
+        labels.push(edge.count.to_string());
+    }
+    if !edge.via.is_empty() {
+        let names: Vec<&str> = edge.via.iter().map(|x| short_name(x)).collect();
+        labels.push(names.join(" > "));
+    }
+    if labels.is_empty() {
+        None
+    } else {
+        Some(labels.join(": "))
+    }
+}
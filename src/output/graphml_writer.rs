@@ -0,0 +1,156 @@
+use super::cluster_writer::{self, indent, ClusterSyntax};
+use super::graph_data::{short_name, GraphData, Node};
+use super::module_tree::ClassPathTree;
+use std::io;
+
+/// Writes the graph in GraphML. (for yEd, Gephi, etc.)
+/// Modules and types become group nodes with a nested graph following `tree`.
+/// Nodes have the attributes label, path, module, kind, visibility and location,
+/// and edges have the number of calls as weight.
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    tree: &ClassPathTree,
+    output: &mut W,
+) -> io::Result<()> {
+    output.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="path" for="node" attr.name="path" attr.type="string"/>
+  <key id="module" for="node" attr.name="module" attr.type="string"/>
+  <key id="kind" for="node" attr.name="kind" attr.type="string"/>
+  <key id="visibility" for="node" attr.name="visibility" attr.type="string"/>
+  <key id="location" for="node" attr.name="location" attr.type="string"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="int"/>
+  <graph id="G" edgedefault="directed">
+"#,
+    )?;
+    output.write_all(cluster_writer::write_clusters(graph, tree, &GraphMlSyntax).as_bytes())?;
+    for (i, edge) in graph.edges.iter().enumerate() {
+        writeln!(
+            output,
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"weight\">{}</data></edge>",
+            i,
+            graph.node_id(&edge.callinfo.caller),
+            graph.node_id(&edge.callinfo.callee),
+            edge.count
+        )?;
+    }
+    output.write_all(b"  </graph>\n</graphml>\n")?;
+
+    Ok(())
+}
+
+struct GraphMlSyntax;
+
+impl ClusterSyntax for GraphMlSyntax {
+    fn start_cluster(&self, cluster_id: usize, name: &str, depth: usize) -> String {
+        let indent = indent(depth);
+        format!(
+            "{}<node id=\"c{}\">\n{}  <data key=\"label\">{}</data>\n{}  <graph id=\"c{}:\" edgedefault=\"directed\">\n",
+            indent,
+            cluster_id,
+            indent,
+            escape(name),
+            indent,
+            cluster_id
+        )
+    }
+
+    fn end_cluster(&self, depth: usize) -> String {
+        let indent = indent(depth);
+        format!("{}  </graph>\n{}</node>\n", indent, indent)
+    }
+
+    fn node(&self, node: &Node, depth: usize) -> String {
+        let indent = indent(depth);
+        let mut result = format!("{}<node id=\"n{}\">\n", indent, node.id);
+        let location = node.location();
+        let data = [
+            ("label", short_name(&node.path)),
+            ("path", &node.path),
+            ("module", node.module()),
+            ("kind", node.kind.as_str()),
+            ("visibility", node.visibility()),
+            ("location", &location),
+        ];
+        for (key, value) in data.iter() {
+            if !value.is_empty() {
+                result += &format!(
+                    "{}  <data key=\"{}\">{}</data>\n",
+                    indent,
+                    key,
+                    escape(value)
+                );
+            }
+        }
+        result += &format!("{}</node>\n", indent);
+        result
+    }
+}
+
+/// Escapes the characters which have a meaning in XML.
+pub fn escape(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Granularity;
+    use crate::test_util;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("Type<'a, T>::f&\""),
+            "Type&lt;&apos;a, T&gt;::f&amp;&quot;"
+        );
+    }
+
+    #[test]
+    fn test_write_graph() {
+        let edges = test_util::edges(&[("k::f", "g")]);
+        let tree = crate::output::make_classpath_tree(&edges);
+        let graph = GraphData::new(edges, &[], Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, &tree, &mut result).unwrap();
+        let result = String::from_utf8(result).unwrap();
+
+        let body = &result[result.find("  <graph id=\"G\"").unwrap()..];
+        let expect = r#"  <graph id="G" edgedefault="directed">
+    <node id="n0">
+      <data key="label">g</data>
+      <data key="path">g</data>
+      <data key="kind">external</data>
+    </node>
+    <node id="c0">
+      <data key="label">k</data>
+      <graph id="c0:" edgedefault="directed">
+        <node id="n1">
+          <data key="label">f</data>
+          <data key="path">k::f</data>
+          <data key="module">k</data>
+          <data key="kind">external</data>
+        </node>
+      </graph>
+    </node>
+    <edge id="e0" source="n1" target="n0"><data key="weight">1</data></edge>
+  </graph>
+</graphml>
+"#;
+        assert_eq!(body, expect);
+    }
+}
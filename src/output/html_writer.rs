@@ -0,0 +1,44 @@
+use super::graph_data::GraphData;
+use super::json_writer;
+use crate::filter::Granularity;
+use std::io;
+
+/// Viewer page. `{{GRAPH_DATA}}` is replaced with the graph in the JSON format.
+const TEMPLATE: &str = include_str!("html_viewer.html");
+
+/// Writes a self-contained HTML file which shows the graph with an interactive viewer.
+/// The graph is embedded in the JSON format, so the file works offline.
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    granularity: Granularity,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut json: Vec<u8> = Vec::new();
+    json_writer::write_graph(graph, granularity, &mut json)?;
+    let json = String::from_utf8_lossy(&json);
+    // "</script>" in a name must not close the script element
+    let json = json.replace("</", "<\\/");
+
+    let html = TEMPLATE.replacen("{{GRAPH_DATA}}", json.trim_end(), 1);
+    output.write_all(html.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn test_write_graph() {
+        let edges = test_util::edges(&[("k::f", "k::</script>")]);
+        let graph = GraphData::new(edges, &[], Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, Granularity::Function, &mut result).unwrap();
+        let result = String::from_utf8(result).unwrap();
+
+        assert!(!result.contains("{{GRAPH_DATA}}"));
+        assert!(result.contains(r#""path": "k::<\/script>""#));
+        assert_eq!(result.matches("</script>").count(), 2);
+    }
+}
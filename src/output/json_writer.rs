@@ -0,0 +1,154 @@
+use super::graph_data::{GraphData, Node};
+use crate::call_info::{CallEdge, Location};
+use crate::filter::Granularity;
+use std::io;
+
+/// Version of the JSON format. See doc/json_format.md.
+pub const SCHEMA_VERSION: usize = 1;
+
+/// Writes the graph in the JSON format described in doc/json_format.md.
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    granularity: Granularity,
+    output: &mut W,
+) -> io::Result<()> {
+    writeln!(output, "{{")?;
+    writeln!(output, "  \"schema_version\": {},", SCHEMA_VERSION)?;
+    writeln!(
+        output,
+        "  \"granularity\": {},",
+        string(granularity.as_str())
+    )?;
+
+    writeln!(output, "  \"nodes\": [")?;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let separator = if i + 1 < graph.nodes.len() { "," } else { "" };
+        writeln!(output, "    {}{}", node_object(node), separator)?;
+    }
+    writeln!(output, "  ],")?;
+
+    writeln!(output, "  \"edges\": [")?;
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let separator = if i + 1 < graph.edges.len() { "," } else { "" };
+        writeln!(output, "    {}{}", edge_object(graph, edge), separator)?;
+    }
+    writeln!(output, "  ]")?;
+    writeln!(output, "}}")?;
+
+    Ok(())
+}
+
+fn node_object(node: &Node) -> String {
+    let (location, metadata) = match &node.definition {
+        Some(function) => (
+            location_object(&function.location),
+            format!(
+                "{{\"public\": {}, \"test\": {}, \"no_mangle\": {}, \"extern\": {}, \"async\": {}, \"unsafe\": {}}}",
+                function.is_public,
+                function.is_test,
+                function.is_no_mangle,
+                function.is_extern,
+                function.is_async,
+                function.is_unsafe
+            ),
+        ),
+        None => ("null".to_string(), "{}".to_string()),
+    };
+    format!(
+        "{{\"id\": {}, \"path\": {}, \"kind\": {}, \"location\": {}, \"metadata\": {}}}",
+        node.id,
+        string(&node.path),
+        string(node.kind.as_str()),
+        location,
+        metadata
+    )
+}
+
+fn edge_object(graph: &GraphData, edge: &CallEdge) -> String {
+    let locations: Vec<String> = edge.locations.iter().map(location_object).collect();
+    let via: Vec<String> = edge.via.iter().map(|x| string(x)).collect();
+    format!(
+        "{{\"caller\": {}, \"callee\": {}, \"kind\": {}, \"count\": {}, \"locations\": [{}], \"via\": [{}]}}",
+        graph.node_id(&edge.callinfo.caller),
+        graph.node_id(&edge.callinfo.callee),
+        string(edge.kind.as_str()),
+        edge.count,
+        locations.join(", "),
+        via.join(", ")
+    )
+}
+
+fn location_object(location: &Location) -> String {
+    format!(
+        "{{\"file\": {}, \"line\": {}}}",
+        string(&location.file.to_string_lossy()),
+        location.line
+    )
+}
+
+/// Returns `value` as a JSON string literal.
+pub fn string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call_info::{CallInfo, CallKind};
+    use crate::test_util;
+
+    #[test]
+    fn test_string() {
+        assert_eq!(string("a::b"), "\"a::b\"");
+        assert_eq!(string("\"x\"\\\n"), "\"\\\"x\\\"\\\\\\n\"");
+        assert_eq!(string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn test_write_graph() {
+        let location = Location {
+            file: "src/main.rs".into(),
+            line: 3,
+        };
+        let edges = vec![CallEdge::new_call_site(
+            CallInfo {
+                caller: "k::main".to_string(),
+                callee: "k::Type::new".to_string(),
+            },
+            CallKind::Function,
+            location.clone(),
+        )];
+        let functions = vec![test_util::function("k::main", 2)];
+        let graph = GraphData::new(edges, &functions, Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, Granularity::Function, &mut result).unwrap();
+
+        let expect = r#"{
+  "schema_version": 1,
+  "granularity": "function",
+  "nodes": [
+    {"id": 0, "path": "k::Type::new", "kind": "external", "location": null, "metadata": {}},
+    {"id": 1, "path": "k::main", "kind": "function", "location": {"file": "src/main.rs", "line": 2}, "metadata": {"public": false, "test": false, "no_mangle": false, "extern": false, "async": false, "unsafe": false}}
+  ],
+  "edges": [
+    {"caller": 1, "callee": 0, "kind": "function", "count": 1, "locations": [{"file": "src/main.rs", "line": 3}], "via": []}
+  ]
+}
+"#;
+        assert_eq!(String::from_utf8(result).unwrap(), expect);
+    }
+}
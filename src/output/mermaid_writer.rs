@@ -0,0 +1,111 @@
+use super::cluster_writer::{self, indent, ClusterSyntax};
+use super::graph_data::{edge_label, short_name, GraphData, Node};
+use super::module_tree::ClassPathTree;
+use std::io;
+
+/// Writes the graph as a Mermaid flowchart.
+/// Modules and types become nested `subgraph` blocks following `tree`,
+/// and each node is written inside its innermost subgraph.
+/// Node and subgraph ids are generated (`n0`, `c0`, ...), so any name is safe as a label.
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    tree: &ClassPathTree,
+    output: &mut W,
+) -> io::Result<()> {
+    output.write_all(b"flowchart LR\n")?;
+    output.write_all(cluster_writer::write_clusters(graph, tree, &MermaidSyntax).as_bytes())?;
+    for edge in &graph.edges {
+        let caller = graph.node_id(&edge.callinfo.caller);
+        let callee = graph.node_id(&edge.callinfo.callee);
+        match edge_label(edge) {
+            Some(text) => writeln!(output, "    n{} -->|{}| n{}", caller, label(&text), callee)?,
+            None => writeln!(output, "    n{} --> n{}", caller, callee)?,
+        }
+    }
+
+    Ok(())
+}
+
+struct MermaidSyntax;
+
+impl ClusterSyntax for MermaidSyntax {
+    fn start_cluster(&self, cluster_id: usize, name: &str, depth: usize) -> String {
+        format!(
+            "{}subgraph c{} [{}]\n",
+            indent(depth),
+            cluster_id,
+            label(name)
+        )
+    }
+
+    fn end_cluster(&self, depth: usize) -> String {
+        format!("{}end\n", indent(depth))
+    }
+
+    fn node(&self, node: &Node, depth: usize) -> String {
+        format!(
+            "{}n{}[{}]\n",
+            indent(depth),
+            node.id,
+            label(short_name(&node.path))
+        )
+    }
+}
+
+/// Returns a quoted label. Characters with a meaning in Mermaid are written as entity codes.
+fn label(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("#quot;"),
+            '#' => result.push_str("#35;"),
+            '<' => result.push_str("#lt;"),
+            '>' => result.push_str("#gt;"),
+            '\n' | '\r' => result.push(' '),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Granularity;
+    use crate::test_util;
+
+    #[test]
+    fn test_write_graph() {
+        let edges = test_util::edges(&[
+            ("k::a::f", "k::a::Type<T>::g"),
+            ("k::a::f", "k::b::h"),
+            ("main", "k::a::f"),
+        ]);
+
+        let tree = crate::output::make_classpath_tree(&edges);
+        let graph = GraphData::new(edges, &[], Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, &tree, &mut result).unwrap();
+
+        let expect = r#"flowchart LR
+    n3["main"]
+    subgraph c0 ["k"]
+        subgraph c1 ["a"]
+            n1["f"]
+            subgraph c2 ["Type#lt;T#gt;"]
+                n0["g"]
+            end
+        end
+        subgraph c3 ["b"]
+            n2["h"]
+        end
+    end
+    n1 --> n0
+    n1 --> n2
+    n3 --> n1
+"#;
+        assert_eq!(String::from_utf8(result).unwrap(), expect);
+    }
+}
@@ -38,6 +38,7 @@ impl ClassPathTree {
     /// By inputting the full path name of a function (e.g., Package::Module::ClassA), you can construct the class tree structure.
     /// e.g. PackageName -> ModuleName -> ClassA
     ///                                -> ClassB
+    /// Children are kept sorted by name, so the order doesn't depend on the order of push().
     pub fn push(&self, function_names: &[&str]) {
         if function_names.is_empty() {
             return;
@@ -58,7 +59,9 @@ impl ClassPathTree {
 
         let new_node = ClassPathTree::create_node(function_names[0]);
         new_node.push(&function_names[1..]);
-        self.edges.borrow_mut().push(new_node);
+        let mut edges = self.edges.borrow_mut();
+        let idx = edges.partition_point(|x| x.node.as_str() < function_names[0]);
+        edges.insert(idx, new_node);
     }
 
     /// Traverses the tree in preorder (root → children) and calls the provided interface methods.
@@ -0,0 +1,100 @@
+use super::cluster_writer::{self, indent, ClusterSyntax};
+use super::graph_data::{edge_label, short_name, GraphData, Node};
+use super::module_tree::ClassPathTree;
+use std::io;
+
+/// Writes the graph as a PlantUML component diagram.
+/// Modules and types become nested `package` blocks following `tree`, functions become components
+/// and calls become arrows. Components are referred to by generated aliases (`n0`, `n1`, ...).
+pub fn write_graph<W: io::Write>(
+    graph: &GraphData,
+    tree: &ClassPathTree,
+    output: &mut W,
+) -> io::Result<()> {
+    output.write_all(b"@startuml\nleft to right direction\n")?;
+    output.write_all(cluster_writer::write_clusters(graph, tree, &PlantUmlSyntax).as_bytes())?;
+    for edge in &graph.edges {
+        let caller = graph.node_id(&edge.callinfo.caller);
+        let callee = graph.node_id(&edge.callinfo.callee);
+        match edge_label(edge) {
+            Some(text) => writeln!(output, "n{} --> n{} : {}", caller, callee, label(&text))?,
+            None => writeln!(output, "n{} --> n{}", caller, callee)?,
+        }
+    }
+    output.write_all(b"@enduml\n")?;
+
+    Ok(())
+}
+
+struct PlantUmlSyntax;
+
+impl ClusterSyntax for PlantUmlSyntax {
+    fn start_cluster(&self, cluster_id: usize, name: &str, depth: usize) -> String {
+        format!(
+            "{}package \"{}\" as c{} {{\n",
+            indent(depth - 1),
+            label(name),
+            cluster_id
+        )
+    }
+
+    fn end_cluster(&self, depth: usize) -> String {
+        format!("{}}}\n", indent(depth - 1))
+    }
+
+    fn node(&self, node: &Node, depth: usize) -> String {
+        format!(
+            "{}component \"{}\" as n{}\n",
+            indent(depth - 1),
+            label(short_name(&node.path)),
+            node.id
+        )
+    }
+}
+
+/// PlantUML has no escape for double quotes in names, so they are replaced with single quotes.
+fn label(text: &str) -> String {
+    text.replace('"', "'").replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Granularity;
+    use crate::test_util;
+
+    #[test]
+    fn test_write_graph() {
+        let mut edges = test_util::edges(&[
+            ("k::a::f", "k::a::g"),
+            ("k::a::f", "k::b::h"),
+            ("main", "k::a::f"),
+        ]);
+        edges[1].count = 2;
+
+        let tree = crate::output::make_classpath_tree(&edges);
+        let graph = GraphData::new(edges, &[], Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_graph(&graph, &tree, &mut result).unwrap();
+
+        let expect = r#"@startuml
+left to right direction
+component "main" as n3
+package "k" as c0 {
+    package "a" as c1 {
+        component "f" as n0
+        component "g" as n1
+    }
+    package "b" as c2 {
+        component "h" as n2
+    }
+}
+n0 --> n1
+n0 --> n2 : 2
+n3 --> n0
+@enduml
+"#;
+        assert_eq!(String::from_utf8(result).unwrap(), expect);
+    }
+}
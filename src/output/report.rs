@@ -0,0 +1,173 @@
+use super::json_writer;
+use super::table_writer::csv_field;
+use crate::graph::{CycleReport, DeadCodeReport, EdgeDiff, NodeMetrics};
+use std::io;
+
+/// File format of the metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetricsFormat {
+    Csv,
+    Json,
+}
+
+/// Writes the cycles found in the call graph as plain text.
+pub fn write_cycle_report<W: io::Write>(report: &CycleReport, output: &mut W) -> io::Result<()> {
+    writeln!(
+        output,
+        "direct recursion: {}",
+        report.direct_recursions.len()
+    )?;
+    for name in &report.direct_recursions {
+        writeln!(output, "  {}", name)?;
+    }
+
+    writeln!(
+        output,
+        "mutual recursion: {}",
+        report.mutual_recursions.len()
+    )?;
+    for group in &report.mutual_recursions {
+        writeln!(output, "  {}", group.join(" <-> "))?;
+    }
+
+    writeln!(output, "module cycles: {}", report.module_cycles.len())?;
+    for group in &report.module_cycles {
+        writeln!(output, "  {}", group.join(" <-> "))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the unreachable functions as "file:line: name",
+/// followed by the functions reachable only from the tests as "file:line: name (test only)".
+pub fn write_dead_code_report<W: io::Write>(
+    report: &DeadCodeReport,
+    output: &mut W,
+) -> io::Result<()> {
+    for function in &report.unreachable {
+        writeln!(output, "{}: {}", function.location, function.name)?;
+    }
+    for function in &report.test_only {
+        writeln!(
+            output,
+            "{}: {} (test only)",
+            function.location, function.name
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes one name per line.
+pub fn write_names<W: io::Write>(names: &[String], output: &mut W) -> io::Result<()> {
+    for name in names {
+        writeln!(output, "{}", name)?;
+    }
+    Ok(())
+}
+
+/// Writes the call chains as "a -> b -> c".
+pub fn write_paths<W: io::Write>(paths: &[Vec<String>], output: &mut W) -> io::Result<()> {
+    for path in paths {
+        writeln!(output, "{}", path.join(" -> "))?;
+    }
+    Ok(())
+}
+
+/// Writes the edges as "+ caller -> callee" and "- caller -> callee".
+pub fn write_diff<W: io::Write>(diff: &EdgeDiff, output: &mut W) -> io::Result<()> {
+    for (caller, callee) in &diff.removed {
+        writeln!(output, "- {} -> {}", caller, callee)?;
+    }
+    for (caller, callee) in &diff.added {
+        writeln!(output, "+ {} -> {}", caller, callee)?;
+    }
+    Ok(())
+}
+
+pub fn write_metrics<W: io::Write>(
+    metrics: &[NodeMetrics],
+    format: MetricsFormat,
+    output: &mut W,
+) -> io::Result<()> {
+    match format {
+        MetricsFormat::Csv => write_metrics_csv(metrics, output),
+        MetricsFormat::Json => write_metrics_json(metrics, output),
+    }
+}
+
+fn write_metrics_csv<W: io::Write>(metrics: &[NodeMetrics], output: &mut W) -> io::Result<()> {
+    writeln!(
+        output,
+        "level,name,in_degree,out_degree,reach,betweenness,depth"
+    )?;
+    for m in metrics {
+        writeln!(
+            output,
+            "{},{},{},{},{},{:.3},{}",
+            m.level.as_str(),
+            csv_field(&m.name),
+            m.in_degree,
+            m.out_degree,
+            m.reach,
+            m.betweenness,
+            m.depth.map(|d| d.to_string()).unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+fn write_metrics_json<W: io::Write>(metrics: &[NodeMetrics], output: &mut W) -> io::Result<()> {
+    writeln!(output, "[")?;
+    for (i, m) in metrics.iter().enumerate() {
+        let separator = if i + 1 < metrics.len() { "," } else { "" };
+        writeln!(
+            output,
+            "  {{\"level\": {}, \"name\": {}, \"in_degree\": {}, \"out_degree\": {}, \"reach\": {}, \"betweenness\": {:.3}, \"depth\": {}}}{}",
+            json_writer::string(m.level.as_str()),
+            json_writer::string(&m.name),
+            m.in_degree,
+            m.out_degree,
+            m.reach,
+            m.betweenness,
+            m.depth.map(|d| d.to_string()).unwrap_or_else(|| "null".to_string()),
+            separator
+        )?;
+    }
+    writeln!(output, "]")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Granularity;
+
+    #[test]
+    fn test_write_metrics() {
+        let metrics = vec![NodeMetrics {
+            level: Granularity::Function,
+            name: "k::Type<A, B>::f".to_string(),
+            in_degree: 1,
+            out_degree: 2,
+            reach: 3,
+            betweenness: 0.5,
+            depth: None,
+        }];
+
+        let mut csv: Vec<u8> = Vec::new();
+        write_metrics(&metrics, MetricsFormat::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "level,name,in_degree,out_degree,reach,betweenness,depth\n\
+             function,\"k::Type<A, B>::f\",1,2,3,0.500,\n"
+        );
+
+        let mut json: Vec<u8> = Vec::new();
+        write_metrics(&metrics, MetricsFormat::Json, &mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "[\n  {\"level\": \"function\", \"name\": \"k::Type<A, B>::f\", \"in_degree\": 1, \
+             \"out_degree\": 2, \"reach\": 3, \"betweenness\": 0.500, \"depth\": null}\n]\n"
+        );
+    }
+}
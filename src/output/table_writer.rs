@@ -0,0 +1,144 @@
+use super::graph_data::GraphData;
+use std::io;
+
+/// Separator of the fields in the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    Comma,
+    Tab,
+}
+
+impl Separator {
+    fn field(&self, value: &str) -> String {
+        match self {
+            Separator::Comma => csv_field(value),
+            // TSV has no quoting
+            Separator::Tab => value.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+
+    fn row(&self, fields: &[&str]) -> String {
+        let fields: Vec<String> = fields.iter().map(|x| self.field(x)).collect();
+        let separator = match self {
+            Separator::Comma => ",",
+            Separator::Tab => "\t",
+        };
+        fields.join(separator) + "\n"
+    }
+}
+
+/// Writes one row per edge: caller, callee, kind, count, file, line.
+/// file and line are those of the first call site, or empty if unknown.
+pub fn write_edges<W: io::Write>(
+    graph: &GraphData,
+    separator: Separator,
+    output: &mut W,
+) -> io::Result<()> {
+    output.write_all(
+        separator
+            .row(&["caller", "callee", "kind", "count", "file", "line"])
+            .as_bytes(),
+    )?;
+    for edge in &graph.edges {
+        let (file, line) = match edge.locations.first() {
+            Some(location) => (
+                location.file.display().to_string(),
+                location.line.to_string(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let row = separator.row(&[
+            &edge.callinfo.caller,
+            &edge.callinfo.callee,
+            edge.kind.as_str(),
+            &edge.count.to_string(),
+            &file,
+            &line,
+        ]);
+        output.write_all(row.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes one row per node: path, kind, visibility, file, line.
+pub fn write_nodes<W: io::Write>(
+    graph: &GraphData,
+    separator: Separator,
+    output: &mut W,
+) -> io::Result<()> {
+    output.write_all(
+        separator
+            .row(&["path", "kind", "visibility", "file", "line"])
+            .as_bytes(),
+    )?;
+    for node in &graph.nodes {
+        let (file, line) = match &node.definition {
+            Some(function) => (
+                function.location.file.display().to_string(),
+                function.location.line.to_string(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let row = separator.row(&[
+            &node.path,
+            node.kind.as_str(),
+            node.visibility(),
+            &file,
+            &line,
+        ]);
+        output.write_all(row.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Quotes the value if it contains a comma, a quote or a line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call_info::{CallEdge, CallInfo, CallKind, Location};
+    use crate::filter::Granularity;
+    use crate::test_util;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_write_table() {
+        let location = Location {
+            file: PathBuf::from("src/main.rs"),
+            line: 3,
+        };
+        let mut edge = CallEdge::new_call_site(
+            CallInfo {
+                caller: "main".to_string(),
+                callee: "Vec<T, A>::push".to_string(),
+            },
+            CallKind::Method,
+            location,
+        );
+        edge.count = 2;
+        let functions = vec![test_util::function("main", 3)];
+        let graph = GraphData::new(vec![edge], &functions, Granularity::Function);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_edges(&graph, Separator::Comma, &mut result).unwrap();
+        let expect = "caller,callee,kind,count,file,line
+main,\"Vec<T, A>::push\",method,2,src/main.rs,3
+";
+        assert_eq!(String::from_utf8(result).unwrap(), expect);
+
+        let mut result: Vec<u8> = Vec::new();
+        write_nodes(&graph, Separator::Tab, &mut result).unwrap();
+        let expect = "path\tkind\tvisibility\tfile\tline
+Vec<T, A>::push\texternal\t\t\t
+main\tfunction\tprivate\tsrc/main.rs\t3
+";
+        assert_eq!(String::from_utf8(result).unwrap(), expect);
+    }
+}
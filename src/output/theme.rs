@@ -0,0 +1,159 @@
+use std::error;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ThemeError {
+    #[error("invalid theme value: {0}")]
+    InvalidValue(String),
+}
+
+/// Styles of the DOT output.
+///
+/// A theme file overrides some of the defaults:
+///
+/// ```toml
+/// [shape]
+/// function = "box"
+/// method = "ellipse"
+/// trait_method = "hexagon"
+/// closure = "octagon"
+/// external = "component"
+/// std = "plaintext"
+/// group = "folder"
+///
+/// [color]
+/// palette = ["#cfe2f3", "#d9ead3"]
+/// unsafe = "red"
+///
+/// [edge]
+/// dynamic = "dashed"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub function_shape: String,
+    pub method_shape: String,
+    pub trait_method_shape: String,
+    pub closure_shape: String,
+    /// functions whose definition was not found in the project.
+    pub external_shape: String,
+    /// functions of std, core and alloc.
+    pub std_shape: String,
+    /// types, modules and crates at a coarse granularity.
+    pub group_shape: String,
+    /// fill colors of the top-level modules, used in turn.
+    pub palette: Vec<String>,
+    /// border color of unsafe functions. (async functions have a double border)
+    pub unsafe_color: String,
+    /// line style of the calls on trait objects.
+    pub dynamic_edge_style: String,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::new()
+    }
+}
+
+impl Theme {
+    pub fn new() -> Theme {
+        Theme {
+            function_shape: "box".to_string(),
+            method_shape: "ellipse".to_string(),
+            trait_method_shape: "hexagon".to_string(),
+            closure_shape: "octagon".to_string(),
+            external_shape: "component".to_string(),
+            std_shape: "plaintext".to_string(),
+            group_shape: "folder".to_string(),
+            palette: [
+                "#cfe2f3", "#d9ead3", "#fff2cc", "#f4cccc", "#d9d2e9", "#fce5cd", "#d0e0e3",
+                "#ead1dc",
+            ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect(),
+            unsafe_color: "red".to_string(),
+            dynamic_edge_style: "dashed".to_string(),
+        }
+    }
+
+    /// Reads the theme file. Missing keys keep the default values.
+    pub fn load(path: &Path) -> Result<Theme, Box<dyn error::Error>> {
+        let value: toml::Value = fs::read_to_string(path)?.parse()?;
+        let mut theme = Theme::new();
+        theme.apply(&value)?;
+        Ok(theme)
+    }
+
+    /// Overrides the styles with the keys found in `value`.
+    pub fn apply(&mut self, value: &toml::Value) -> Result<(), ThemeError> {
+        let shapes = [
+            ("function", &mut self.function_shape),
+            ("method", &mut self.method_shape),
+            ("trait_method", &mut self.trait_method_shape),
+            ("closure", &mut self.closure_shape),
+            ("external", &mut self.external_shape),
+            ("std", &mut self.std_shape),
+            ("group", &mut self.group_shape),
+        ];
+        for (key, field) in shapes {
+            set_string(value, "shape", key, field)?;
+        }
+        set_string(value, "color", "unsafe", &mut self.unsafe_color)?;
+        set_string(value, "edge", "dynamic", &mut self.dynamic_edge_style)?;
+
+        if let Some(palette) = value.get("color").and_then(|x| x.get("palette")) {
+            let colors = palette
+                .as_array()
+                .ok_or_else(|| ThemeError::InvalidValue("color.palette".to_string()))?;
+            self.palette = colors
+                .iter()
+                .map(|x| x.as_str().map(String::from))
+                .collect::<Option<Vec<String>>>()
+                .filter(|x| !x.is_empty())
+                .ok_or_else(|| ThemeError::InvalidValue("color.palette".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+fn set_string(
+    value: &toml::Value,
+    table: &str,
+    key: &str,
+    field: &mut String,
+) -> Result<(), ThemeError> {
+    if let Some(x) = value.get(table).and_then(|x| x.get(key)) {
+        *field = x
+            .as_str()
+            .ok_or_else(|| ThemeError::InvalidValue(format!("{}.{}", table, key)))?
+            .to_string();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let value: toml::Value = r##"
+            [shape]
+            method = "box"
+            [color]
+            palette = ["#ffffff"]
+        "##
+        .parse()
+        .unwrap();
+        let mut theme = Theme::new();
+        theme.apply(&value).unwrap();
+        assert_eq!(theme.method_shape, "box");
+        assert_eq!(theme.function_shape, "box");
+        assert_eq!(theme.palette, vec!["#ffffff".to_string()]);
+
+        let value: toml::Value = "[shape]\nmethod = 1".parse().unwrap();
+        assert!(Theme::new().apply(&value).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::call_info::CallEdge;
+use crate::graph::CallGraph;
+use std::io;
+
+/// Writes the call hierarchy as an indented tree like `cargo tree`.
+/// `roots` are full names or their last segments (e.g. "main", "Type::method").
+/// Without roots, the tree starts from every function which is not called by others.
+/// With `invert`, the children of a node are its callers instead of its callees.
+/// A node expanded before is marked `(*)`, a call back into the current path `(cycle)`.
+pub fn write_tree<W: io::Write>(
+    edges: &[CallEdge],
+    roots: &[String],
+    invert: bool,
+    output: &mut W,
+) -> io::Result<()> {
+    let graph = CallGraph::new(edges);
+    let children = |node: usize| {
+        if invert {
+            graph.callers(node)
+        } else {
+            graph.callees(node)
+        }
+    };
+
+    let mut root_nodes: Vec<usize> = Vec::new();
+    for root in roots {
+        let found = graph.find(root);
+        if found.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("root not found: {}", root),
+            ));
+        }
+        root_nodes.extend(found);
+    }
+    if roots.is_empty() {
+        root_nodes = (0..graph.len())
+            .filter(|&node| {
+                let parents = if invert {
+                    graph.callees(node)
+                } else {
+                    graph.callers(node)
+                };
+                parents.is_empty()
+            })
+            .collect();
+        // every node is in a cycle
+        if root_nodes.is_empty() {
+            root_nodes = (0..graph.len()).collect();
+        }
+    }
+
+    let mut tree = TreeWriter {
+        graph: &graph,
+        children: &children,
+        expanded: vec![false; graph.len()],
+        on_path: vec![false; graph.len()],
+        result: String::new(),
+    };
+    for (i, &root) in root_nodes.iter().enumerate() {
+        if i > 0 {
+            tree.result.push('\n');
+        }
+        tree.write_node(root, "", "");
+    }
+    output.write_all(tree.result.as_bytes())
+}
+
+struct TreeWriter<'a> {
+    graph: &'a CallGraph,
+    children: &'a dyn Fn(usize) -> &'a [usize],
+    expanded: Vec<bool>,
+    on_path: Vec<bool>,
+    result: String,
+}
+
+impl<'a> TreeWriter<'a> {
+    /// `branch` is written before the name, `prefix` before the lines of the children.
+    fn write_node(&mut self, node: usize, branch: &str, prefix: &str) {
+        let name = self.graph.name(node);
+        if self.on_path[node] {
+            self.result += &format!("{}{} (cycle)\n", branch, name);
+            return;
+        }
+        let children = (self.children)(node);
+        if self.expanded[node] && !children.is_empty() {
+            self.result += &format!("{}{} (*)\n", branch, name);
+            return;
+        }
+        self.result += &format!("{}{}\n", branch, name);
+
+        self.expanded[node] = true;
+        self.on_path[node] = true;
+        for (i, &child) in children.iter().enumerate() {
+            let (branch, indent) = if i + 1 < children.len() {
+                ("├── ", "│   ")
+            } else {
+                ("└── ", "    ")
+            };
+            self.write_node(
+                child,
+                &format!("{}{}", prefix, branch),
+                &format!("{}{}", prefix, indent),
+            );
+        }
+        self.on_path[node] = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::edges;
+
+    fn tree(edges: &[CallEdge], roots: &[&str], invert: bool) -> String {
+        let roots: Vec<String> = roots.iter().map(|x| x.to_string()).collect();
+        let mut result: Vec<u8> = Vec::new();
+        write_tree(edges, &roots, invert, &mut result).unwrap();
+        String::from_utf8(result).unwrap()
+    }
+
+    #[test]
+    fn test_write_tree() {
+        let edges = edges(&[
+            ("main", "k::a"),
+            ("main", "k::b"),
+            ("k::a", "k::c"),
+            ("k::b", "k::a"),
+            ("k::c", "k::c"),
+        ]);
+
+        let expect = "main
+├── k::a
+│   └── k::c
+│       └── k::c (cycle)
+└── k::b
+    └── k::a (*)
+";
+        assert_eq!(tree(&edges, &[], false), expect);
+
+        let expect = "k::c
+├── k::a
+│   ├── k::b
+│   │   └── main
+│   └── main
+└── k::c (cycle)
+";
+        assert_eq!(tree(&edges, &["c"], true), expect);
+
+        let mut result: Vec<u8> = Vec::new();
+        assert!(write_tree(&edges, &["x".to_string()], false, &mut result).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! test_util is a module for the fixtures shared by the tests.
+use crate::call_info::{CallEdge, CallInfo, FunctionInfo, FunctionKind, Location};
+use std::fs;
+use std::path::PathBuf;
+
+/// Edges of a single call for each (caller, callee).
+pub fn edges(pairs: &[(&str, &str)]) -> Vec<CallEdge> {
+    pairs
+        .iter()
+        .map(|(caller, callee)| {
+            CallEdge::new(CallInfo {
+                caller: caller.to_string(),
+                callee: callee.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Private free function at `line` of src/main.rs, without attributes or qualifiers.
+pub fn function(name: &str, line: usize) -> FunctionInfo {
+    FunctionInfo {
+        name: name.to_string(),
+        kind: FunctionKind::Function,
+        location: Location {
+            file: "src/main.rs".into(),
+            line,
+        },
+        is_public: false,
+        is_test: false,
+        is_no_mangle: false,
+        is_extern: false,
+        is_async: false,
+        is_unsafe: false,
+        is_exported: false,
+        is_main: false,
+    }
+}
+
+/// Writes (path, content) files into a new directory under the temporary directory.
+/// `name` has to be unique among the tests, which run in parallel.
+pub fn write_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("callgraph-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
@@ -0,0 +1,286 @@
+//! watch is a module for re-running a command when the source files are modified.
+use crate::{analyzer, run_cached, write_result, Command, Format, Options};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the modification times of the files are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long the server waits for the request of a client.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reloads the page when the version served at /version changes.
+const RELOAD_SCRIPT: &str = r#"<script>
+let servedVersion = null;
+setInterval(() => {
+  fetch('/version').then(r => r.text()).then(v => {
+    if (servedVersion !== null && v !== servedVersion) location.reload();
+    servedVersion = v;
+  }).catch(() => {});
+}, 1000);
+</script>
+"#;
+
+/// Runs `command` every time a source file or Cargo.toml of the project is modified.
+/// If `serve` is given, the HTML viewer is served on the port with live reload instead.
+/// Errors of a run (e.g. a file being edited does not parse) are printed and the watch goes on.
+pub fn watch(
+    directory: &PathBuf,
+    command: &Command,
+    options: &Options,
+    serve: Option<u16>,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut cache = analyzer::Cache::new();
+    let page = match serve {
+        Some(port) => {
+            let listener = TcpListener::bind(("127.0.0.1", port))?;
+            eprintln!("serving on http://127.0.0.1:{}/", port);
+            Some(serve_page(listener))
+        }
+        None => None,
+    };
+
+    // the files are listed by each run, and only their times are checked in between
+    let mut watched = watched_paths(directory, &cache);
+    let mut last: Option<BTreeMap<PathBuf, SystemTime>> = None;
+    loop {
+        let current = modified_times(&watched);
+        if last.as_ref() != Some(&current) {
+            let result = match &page {
+                Some(page) => render_page(directory, command, options, &mut cache, page),
+                None => run_cached(directory, command, options, &mut cache),
+            };
+            if let Err(err) = result {
+                eprintln!("{}", err);
+            }
+            eprintln!("[watching {} for changes]", directory.display());
+
+            // the files modified during the run keep their times from before it, so that the
+            // next poll runs the command again
+            watched = watched_paths(directory, &cache);
+            let mut next = modified_times(&watched);
+            for (path, modified) in next.iter_mut() {
+                if let Some(before) = current.get(path) {
+                    *modified = *before;
+                }
+            }
+            last = Some(next);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Cargo.toml, the source files read by the last run and the directories containing them.
+/// A file added to or removed from a directory changes the time of the directory.
+fn watched_paths(directory: &Path, cache: &analyzer::Cache) -> BTreeSet<PathBuf> {
+    let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+    paths.insert(directory.join("Cargo.toml"));
+    paths.insert(directory.join("src"));
+    for file in cache.sources() {
+        if let Some(parent) = file.parent() {
+            paths.insert(parent.to_path_buf());
+        }
+        paths.insert(file.clone());
+    }
+    paths
+}
+
+/// Modification times of the paths. Paths which can't be read are left out,
+/// and they are noticed when they appear again.
+fn modified_times(paths: &BTreeSet<PathBuf>) -> BTreeMap<PathBuf, SystemTime> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(path).and_then(|x| x.modified()).ok()?;
+            Some((path.clone(), modified))
+        })
+        .collect()
+}
+
+/// Page served by the live reload server.
+struct Page {
+    version: u64,
+    html: String,
+}
+
+fn render_page(
+    directory: &PathBuf,
+    command: &Command,
+    options: &Options,
+    cache: &mut analyzer::Cache,
+    page: &Mutex<Page>,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut data: Vec<u8> = Vec::new();
+    write_result(directory, command, options, Format::Html, &mut data, cache)?;
+    let mut html = String::from_utf8(data)?;
+    match html.rfind("</body>") {
+        Some(idx) => html.insert_str(idx, RELOAD_SCRIPT),
+        None => html.push_str(RELOAD_SCRIPT),
+    }
+
+    let mut page = page.lock().unwrap();
+    page.version += 1;
+    page.html = html;
+    Ok(())
+}
+
+/// Starts a server of the page in another thread. Each client is served by its own thread,
+/// so a client which doesn't send its request doesn't block the others.
+fn serve_page(listener: TcpListener) -> Arc<Mutex<Page>> {
+    let page = Arc::new(Mutex::new(Page {
+        version: 0,
+        html: String::new(),
+    }));
+    let served = Arc::clone(&page);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let page = Arc::clone(&served);
+            // a broken connection only fails that request
+            thread::spawn(move || respond(stream, &page));
+        }
+    });
+    page
+}
+
+fn respond(mut stream: TcpStream, page: &Mutex<Page>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, content_type, body) = {
+        let page = page.lock().unwrap();
+        match path {
+            "/" | "/index.html" => ("200 OK", "text/html; charset=utf-8", page.html.clone()),
+            "/version" => ("200 OK", "text/plain", page.version.to_string()),
+            _ => ("404 Not Found", "text/plain", "not found".to_string()),
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use std::io::Read;
+
+    fn get(address: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(READ_TIMEOUT)).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_watched_paths() {
+        let dir = test_util::write_project(
+            "watch",
+            &[
+                ("Cargo.toml", "[package]\nname = \"pkg\"\n"),
+                ("src/lib.rs", "mod a;\n"),
+                ("src/a.rs", "mod b;\n"),
+                ("src/a/b.rs", "fn f() {}\n"),
+            ],
+        );
+        let mut cache = analyzer::Cache::new();
+        let mut options = analyzer::Options::new();
+        options.targets = Some(crate::Targets::default());
+        analyzer::analyze(&dir, &options, &mut cache).unwrap();
+
+        let watched = watched_paths(&dir, &cache);
+        let names: Vec<String> = watched
+            .iter()
+            .map(|x| x.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Cargo.toml",
+                "src",
+                "src/a",
+                "src/a/b.rs",
+                "src/a.rs",
+                "src/lib.rs"
+            ]
+        );
+
+        // a new file changes the time of its directory
+        let before = modified_times(&watched);
+        thread::sleep(Duration::from_millis(20));
+        fs::write(dir.join("src/a/c.rs"), "").unwrap();
+        let after = modified_times(&watched);
+        assert_ne!(before[&dir.join("src/a")], after[&dir.join("src/a")]);
+        assert_eq!(
+            before[&dir.join("src/lib.rs")],
+            after[&dir.join("src/lib.rs")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_serve_page() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let page = serve_page(listener);
+        {
+            let mut page = page.lock().unwrap();
+            page.version = 3;
+            page.html = "<p>graph</p>".to_string();
+        }
+
+        // a client which doesn't send its request doesn't block the others
+        let _idle = TcpStream::connect(address).unwrap();
+        let response = get(address, "/version");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n3"));
+
+        assert!(get(address, "/").ends_with("\r\n\r\n<p>graph</p>"));
+        assert!(get(address, "/missing").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[test]
+    fn test_render_page() {
+        let dir = test_util::write_project(
+            "render-page",
+            &[
+                ("Cargo.toml", "[package]\nname = \"pkg\"\n"),
+                ("src/lib.rs", "fn a() {\n    b();\n}\nfn b() {}\n"),
+            ],
+        );
+        let page = Mutex::new(Page {
+            version: 0,
+            html: String::new(),
+        });
+        let mut cache = analyzer::Cache::new();
+        let options = Options::new();
+        for _ in 0..2 {
+            render_page(&dir, &Command::Graph, &options, &mut cache, &page).unwrap();
+        }
+
+        let page = page.lock().unwrap();
+        assert_eq!(page.version, 2);
+        assert!(page.html.contains("pkg::b"));
+        assert!(page.html.contains(&format!("{}</body>", RELOAD_SCRIPT)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}